@@ -1,145 +1,283 @@
-use rand::Rng as _;
+use std::borrow::Cow;
+
+use rand::{Rng as _, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+pub mod analysis;
+pub mod braid;
+pub mod cell;
+pub mod editor;
+pub mod exit;
+pub mod generators;
+pub mod grid;
+pub mod levels;
+pub mod mask;
+pub mod packed;
+pub mod parallel;
+pub mod pos;
+pub mod rows;
+pub mod solver;
+pub mod steps;
+pub mod stream;
+pub mod terrain;
+pub mod text;
+pub mod topology;
+mod util;
+pub mod validate;
+pub mod world;
+pub mod wrap;
+
+pub use analysis::Analysis;
+pub use cell::Cell;
+pub use editor::{Edit, Editor};
+pub use exit::ExitPlacement;
+pub use generators::{GenerateError, Generator};
+pub use grid::Grid;
+pub use mask::{Mask, MaskError};
+pub use pos::{OutOfBounds, Pos};
+pub use solver::{Solution, Solver};
+pub use steps::{Generation, Step};
+pub use terrain::Terrain;
+pub use text::TextError;
+pub use validate::Violation;
 
 pub struct Maze {
     x: usize,
     y: usize,
-    grid: Vec<Vec<u8>>, // 0 for walls, 1 for passages
+    grid: Vec<Vec<Cell>>,
     walls: Vec<(usize, usize)>,
-    start: (usize, usize),
-    rng: rand::rngs::ThreadRng,
+    start: Pos,
+    exit: Option<Pos>,
+    exit_placement: ExitPlacement,
+    mask: Option<Mask>,
+    wrap: bool,
+    terrain: Option<Vec<Vec<Terrain>>>, // None until some cell is given one
+    seed: Option<u64>,                  // None when built from a caller supplied rng
+    algorithm: Cow<'static, str>,
+    rng: Box<dyn RngCore + Send>,
 }
 
-pub trait MazeTrait {
+pub trait MazeTrait: Grid {
     fn new(x: usize, y: usize) -> Self;
 
-    fn generate(&mut self);
+    fn with_seed(x: usize, y: usize, seed: u64) -> Self;
 
-    fn insert_at(&mut self, x: usize, y: usize, c: u8);
+    fn with_rng<R: RngCore + Send + 'static>(x: usize, y: usize, rng: R) -> Self;
 
-    fn get_at(&self, nx: usize, ny: usize) -> u8;
+    fn seed(&self) -> Option<u64>;
 
-    fn swap_at(&mut self, xa: usize, ya: usize, xb: usize, yb: usize);
+    fn generate(&mut self);
+
+    fn generate_with(&mut self, generator: &mut dyn Generator) -> Result<(), GenerateError>;
 }
 
 impl MazeTrait for Maze {
     fn new(x: usize, y: usize) -> Self {
-        let mut grid = vec![vec![0; x]; y];
-        let mut rng = rand::rng();
-        let start_x = rng.random_range(1..x - 1);
-        let start_y = rng.random_range(1..y - 1);
-
-        grid[start_y][start_x] = 1;
-        let mut walls = vec![];
-        for &(dx, dy) in &[(0, 1), (1, 0), (0, -1), (-1, 0)] {
-            let nx = (start_x as isize + dx) as usize;
-            let ny = (start_y as isize + dy) as usize;
-            if nx > 0 && ny > 0 && nx < x - 1 && ny < y - 1 {
-                walls.push((nx, ny));
-            }
-        }
+        // Draw the seed up front so even unseeded mazes can be regenerated later
+        Self::with_seed(x, y, rand::rng().random())
+    }
+
+    fn with_seed(x: usize, y: usize, seed: u64) -> Self {
+        // ChaCha's output is fixed by its spec, unlike `StdRng` which may change between rand
+        // releases, so a seed keeps making the same maze
+        let mut maze = Self::with_rng(x, y, ChaCha8Rng::seed_from_u64(seed));
+        maze.seed = Some(seed);
+        maze
+    }
+
+    fn with_rng<R: RngCore + Send + 'static>(x: usize, y: usize, mut rng: R) -> Self {
+        // Start on an odd coordinate so it lines up with the cell lattice used by the generators
+        let start_x = rng.random_range(0..(x - 1) / 2) * 2 + 1;
+        let start_y = rng.random_range(0..(y - 1) / 2) * 2 + 1;
 
         Self {
             x,
             y,
-            grid,
-            walls,
-            start: (start_x, start_y),
-            rng,
+            grid: vec![vec![Cell::Wall; x]; y],
+            walls: vec![],
+            start: Pos::new(start_x, start_y),
+            exit: None,
+            exit_placement: ExitPlacement::None,
+            mask: None,
+            wrap: false,
+            terrain: None,
+            seed: None,
+            algorithm: "none".into(),
+            rng: Box::new(rng),
         }
     }
 
+    fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
     fn generate(&mut self) {
-        let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-
-        while !self.walls.is_empty() {
-            // Select a random wall from the list
-            let idx = self.rng.random_range(0..self.walls.len());
-            let (wx, wy) = self.walls.swap_remove(idx);
-
-            // Count how many adjacent cells are passages
-            let mut adjacent = 0;
-            for &(dx, dy) in directions.iter() {
-                let nx = (wx as isize + dx) as usize;
-                let ny = (wy as isize + dy) as usize;
-                if nx < self.x && ny < self.y && self.grid[ny][nx] == 1 {
-                    adjacent += 1;
-                }
-            }
-
-            // If the wall divides one passage cell from an unvisited cell, convert it
-            if adjacent == 1 {
-                self.grid[wy][wx] = 1;
-
-                // Add neighboring walls of the newly made passage cell
-                for &(dx, dy) in directions.iter() {
-                    let nx = (wx as isize + dx) as usize;
-                    let ny = (wy as isize + dy) as usize;
-                    if nx > 0
-                        && ny > 0
-                        && nx < self.x - 1
-                        && ny < self.y - 1
-                        && self.get_at(nx, ny) == 0
-                    {
-                        self.walls.push((nx, ny));
-                    }
-                }
-            }
-        }
-        self.insert_at(self.start.1, self.start.0, 2);
+        self.generate_with(&mut generators::Prim)
+            .expect("Prim's carves any shape");
     }
 
-    fn insert_at(&mut self, x: usize, y: usize, c: u8) {
-        if x > self.x {
-            panic!("x {} Was above maze max x {}", x, self.x);
-        }
-        if y > self.y {
-            panic!("y {} Was above maze max y {}", y, self.y);
-        }
-        self.grid[x][y] = c;
+    fn generate_with(&mut self, generator: &mut dyn Generator) -> Result<(), GenerateError> {
+        self.supports(generator)?;
+        self.prepare();
+        generator.carve(self);
+        self.finish(generator.name());
+        Ok(())
+    }
+}
+
+impl Grid for Maze {
+    fn size(&self) -> (usize, usize) {
+        (self.x, self.y)
     }
 
-    fn get_at(&self, nx: usize, ny: usize) -> u8 {
-        return self.grid[ny][nx];
+    fn try_get(&self, pos: Pos) -> Result<Cell, OutOfBounds> {
+        self.check(pos)?;
+        Ok(self.grid[pos.y][pos.x])
     }
 
-    fn swap_at(&mut self, xa: usize, ya: usize, xb: usize, yb: usize) {
-        if xa > self.x {
-            panic!("xa {} Was above maze max x {}", xa, self.x);
+    fn try_set(&mut self, pos: Pos, c: Cell) -> Result<Cell, OutOfBounds> {
+        self.check(pos)?;
+        Ok(std::mem::replace(&mut self.grid[pos.y][pos.x], c))
+    }
+
+    // Goes round the edges when the maze wraps
+    fn neighbour(&self, pos: Pos, dx: isize, dy: isize) -> Option<Pos> {
+        if self.wrap {
+            Some(self.around(pos, dx, dy))
+        } else {
+            pos.offset(dx, dy).filter(|&n| self.contains(n))
         }
-        if ya > self.y {
-            panic!("ya {} Was above maze max y {}", ya, self.y);
+    }
+}
+
+impl Maze {
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    // Grid in the byte encoding used before cells were typed
+    pub fn to_bytes(&self) -> Vec<Vec<u8>> {
+        self.grid
+            .iter()
+            .map(|row| row.iter().map(|&c| c.into()).collect())
+            .collect()
+    }
+
+    pub fn from_bytes(rows: &[Vec<u8>]) -> Self {
+        let grid: Vec<Vec<Cell>> = rows
+            .iter()
+            .map(|row| row.iter().map(|&b| b.into()).collect())
+            .collect();
+        let y = grid.len();
+        let x = grid.first().map_or(0, |row| row.len());
+        let start = (0..y)
+            .flat_map(|sy| (0..x).map(move |sx| (sx, sy)))
+            .find(|&(sx, sy)| grid[sy][sx] == Cell::Start)
+            .map_or(Pos::new(1, 1), Pos::from);
+        let exit = (0..y)
+            .flat_map(|ey| (0..x).map(move |ex| (ex, ey)))
+            .find(|&(ex, ey)| grid[ey][ex] == Cell::Exit)
+            .map(Pos::from);
+
+        Self {
+            x,
+            y,
+            grid,
+            walls: vec![],
+            start,
+            exit,
+            exit_placement: ExitPlacement::None,
+            mask: None,
+            wrap: false,
+            terrain: None,
+            seed: None,
+            algorithm: "none".into(),
+            rng: Box::new(ChaCha8Rng::from_rng(&mut rand::rng())),
         }
-        if xb > self.x {
-            panic!("xb {} Was above maze max x {}", xb, self.x);
+    }
+
+    pub fn start(&self) -> Pos {
+        self.start
+    }
+
+    pub fn exit(&self) -> Option<Pos> {
+        self.exit
+    }
+
+    fn supports(&self, generator: &dyn Generator) -> Result<(), GenerateError> {
+        if self.mask.is_some() && !generator.supports_mask() {
+            return Err(GenerateError::Mask(generator.name()));
         }
-        if yb > self.y {
-            panic!("yb {} Was above maze max y {}", yb, self.y);
+        if self.wrap && !generator.supports_wrap() {
+            return Err(GenerateError::Wrap(generator.name()));
         }
-        let a: u8 = self.grid[xa][ya];
-        let b: u8 = self.grid[xb][yb];
+        Ok(())
+    }
+
+    // Back to solid wall, ready to carve
+    fn prepare(&mut self) {
+        self.grid = vec![vec![Cell::Wall; self.x]; self.y];
+        self.walls.clear();
+    }
 
-        self.grid[xa][ya] = b;
-        self.grid[xb][yb] = a;
+    fn finish(&mut self, algorithm: &'static str) {
+        self.mirror_edges();
+        self.algorithm = algorithm.into();
+        self.insert_at(self.start, Cell::Start);
+        self.place_exit();
     }
 }
 
 impl std::fmt::Display for Maze {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for row in &self.grid {
-            for &cell in row {
-                write!(
-                    f,
-                    "{}",
-                    match cell {
-                        0 => "##",
-                        1 => "__",
-                        2 => "[]",
-                        _ => "??",
-                    }
-                )?;
-            }
-            write!(f, "\r\n")?;
-        }
-        Ok(())
+        grid::write_grid(self, f)
+    }
+}
+
+// A maze drawn by hand for tests, one row a line with the indent trimmed off: `#` wall, `S`
+// start, `E` exit, anything else a passage
+#[cfg(test)]
+fn sketch(rows: &str) -> Maze {
+    let bytes: Vec<Vec<u8>> = rows
+        .lines()
+        .map(str::trim)
+        .filter(|row| !row.is_empty())
+        .map(|row| {
+            row.chars()
+                .map(|c| match c {
+                    '#' => Cell::Wall,
+                    'S' => Cell::Start,
+                    'E' => Cell::Exit,
+                    _ => Cell::Passage,
+                })
+                .map(u8::from)
+                .collect()
+        })
+        .collect();
+    Maze::from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_maze() {
+        let mut a = Maze::with_seed(21, 21, 42);
+        let mut b = Maze::with_seed(21, 21, 42);
+        a.generate();
+        b.generate();
+        assert_eq!(a.to_string(), b.to_string());
+        assert_eq!(a.seed(), Some(42));
+
+        let mut c = Maze::with_seed(21, 21, 43);
+        c.generate();
+        assert_ne!(a.to_string(), c.to_string());
+    }
+
+    #[test]
+    fn mazes_from_an_rng_have_no_seed() {
+        let maze = Maze::with_rng(21, 21, ChaCha8Rng::seed_from_u64(1));
+        assert_eq!(maze.seed(), None);
     }
 }
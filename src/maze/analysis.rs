@@ -0,0 +1,168 @@
+use super::{Grid, Maze, Pos, solver::Bfs};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub cells: usize,     // passable cells
+    pub dead_ends: usize, // one way in
+    pub corridors: usize, // two ways, straight through
+    pub turns: usize,     // two ways, around a corner
+    pub junctions: usize, // three or four ways
+    // Average choices onward from a cell that isn't a dead end
+    pub branching_factor: f64,
+    // Steps from the start to the exit, or to the farthest cell when there is no exit
+    pub solution_length: usize,
+    pub solution_ratio: f64, // cells on the solution per passable cell
+    pub solution_junctions: usize,
+    // Average length of a blind alley, low for mazes full of short stubs
+    pub river: f64,
+    pub longest_corridor: usize, // longest run of cells without a branch
+    pub difficulty: f64,
+}
+
+impl Maze {
+    pub fn analyze(&self) -> Analysis {
+        let degree = |p: Pos| self.open_neighbours(p).len();
+        let passable: Vec<Pos> = (0..self.y)
+            .flat_map(|y| (0..self.x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| self.grid[p.y][p.x].is_passable())
+            .collect();
+
+        let (mut dead_ends, mut corridors, mut turns, mut junctions) = (0, 0, 0, 0);
+        let mut onward = 0;
+        for &p in &passable {
+            let next = self.open_neighbours(p);
+            match next.len() {
+                1 => dead_ends += 1,
+                2 if next[0].x == next[1].x || next[0].y == next[1].y => corridors += 1,
+                2 => turns += 1,
+                n if n > 2 => junctions += 1,
+                _ => {}
+            }
+            onward += next.len().saturating_sub(1);
+        }
+        let branching = corridors + turns + junctions;
+        let branching_factor = if branching == 0 {
+            0.0
+        } else {
+            onward as f64 / branching as f64
+        };
+
+        let goal = self.exit.unwrap_or_else(|| self.farthest(self.start).0);
+        let solution = self.solve_with(&mut Bfs, goal);
+        let (solution_length, solution_junctions) = solution.map_or((0, 0), |s| {
+            let forks = s.path.iter().filter(|&&p| degree(p) > 2).count();
+            (s.path.len() - 1, forks)
+        });
+        let solution_ratio = if passable.is_empty() {
+            0.0
+        } else {
+            (solution_length + 1) as f64 / passable.len() as f64
+        };
+
+        // Walk each blind alley back from its dead end to the first fork
+        let mut alley_total = 0;
+        for &p in passable.iter().filter(|&&p| degree(p) == 1) {
+            let (mut previous, mut current) = (p, p);
+            loop {
+                alley_total += 1;
+                let next: Vec<Pos> = self
+                    .open_neighbours(current)
+                    .into_iter()
+                    .filter(|&n| n != previous)
+                    .collect();
+                if next.len() != 1 || degree(next[0]) > 2 {
+                    break;
+                }
+                (previous, current) = (current, next[0]);
+            }
+        }
+        let river = if dead_ends == 0 {
+            0.0
+        } else {
+            alley_total as f64 / dead_ends as f64
+        };
+
+        let longest_corridor = self.longest_corridor();
+
+        // Every fork on the way out is a decision, and long alleys make wrong ones expensive
+        let difficulty = solution_junctions as f64 * (1.0 + river).ln() + solution_ratio * 10.0;
+
+        Analysis {
+            cells: passable.len(),
+            dead_ends,
+            corridors,
+            turns,
+            junctions,
+            branching_factor,
+            solution_length,
+            solution_ratio,
+            solution_junctions,
+            river,
+            longest_corridor,
+            difficulty,
+        }
+    }
+
+    // Longest chain of two-way cells, loops without any fork included
+    fn longest_corridor(&self) -> usize {
+        let two_way =
+            |p: Pos| self.grid[p.y][p.x].is_passable() && self.open_neighbours(p).len() == 2;
+        let mut seen = vec![vec![false; self.x]; self.y];
+        let mut longest = 0;
+
+        for y in 0..self.y {
+            for x in 0..self.x {
+                let p = Pos::new(x, y);
+                if !two_way(p) || seen[y][x] {
+                    continue;
+                }
+                // Flood along the chain in both directions
+                seen[y][x] = true;
+                let mut length = 1;
+                let mut stack = vec![p];
+                while let Some(c) = stack.pop() {
+                    for n in self.open_neighbours(c) {
+                        if two_way(n) && !seen[n.y][n.x] {
+                            seen[n.y][n.x] = true;
+                            length += 1;
+                            stack.push(n);
+                        }
+                    }
+                }
+                longest = longest.max(length);
+            }
+        }
+        longest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::sketch;
+
+    #[test]
+    fn counts_a_small_maze() {
+        let maze = sketch(
+            "#######
+             #S..#.#
+             #.#.#.#
+             #.#...#
+             #.###.#
+             #...#E#
+             #######",
+        );
+        let a = maze.analyze();
+        assert_eq!(a.cells, 17);
+        assert_eq!(a.dead_ends, 3);
+        assert_eq!(a.corridors, 9);
+        assert_eq!(a.turns, 4);
+        assert_eq!(a.junctions, 1);
+        assert_eq!(a.branching_factor, 15.0 / 14.0);
+        assert_eq!(a.solution_length, 8);
+        assert_eq!(a.solution_ratio, 9.0 / 17.0);
+        assert_eq!(a.solution_junctions, 1);
+        // Alleys of 2 and 12 cells off the junction, and the 2 cells down to the exit
+        assert_eq!(a.river, 16.0 / 3.0);
+        assert_eq!(a.longest_corridor, 11);
+    }
+}
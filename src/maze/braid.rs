@@ -0,0 +1,149 @@
+use rand::{Rng as _, seq::SliceRandom};
+
+use super::{Cell, Grid, Maze, Pos};
+
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+impl Maze {
+    // Wall between `p` and the passage two steps away, if knocking it out would join them
+    fn bridge(&self, p: Pos, (dx, dy): (isize, isize)) -> Option<Pos> {
+        let wall = p.offset(dx, dy)?;
+        let beyond = wall.offset(dx, dy)?;
+        let inside = |q: Pos| q.x > 0 && q.y > 0 && q.x + 1 < self.x && q.y + 1 < self.y;
+        (inside(wall)
+            && inside(beyond)
+            && self.grid[wall.y][wall.x].is_wall()
+            && self.grid[beyond.y][beyond.x].is_passable())
+        .then_some(wall)
+    }
+
+    fn is_dead_end(&self, p: Pos) -> bool {
+        self.grid[p.y][p.x].is_passable() && self.open_neighbours(p).len() == 1
+    }
+
+    // Removes roughly `fraction` of the dead ends by opening each into a neighbouring passage,
+    // preferring one that is itself a dead end so a single wall clears two of them.
+    // Returns how many walls were knocked out.
+    pub fn braid(&mut self, fraction: f64) -> usize {
+        let mut ends: Vec<Pos> = (0..self.y)
+            .flat_map(|y| (0..self.x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| self.is_dead_end(p))
+            .collect();
+        ends.shuffle(&mut self.rng);
+
+        let mut removed = 0;
+        for p in ends {
+            // An earlier knockout may already have opened this one up
+            if !self.is_dead_end(p) || !self.rng.random_bool(fraction.clamp(0.0, 1.0)) {
+                continue;
+            }
+            let mut options: Vec<(Pos, Pos)> = DIRECTIONS
+                .iter()
+                .filter_map(|&(dx, dy)| {
+                    let wall = self.bridge(p, (dx, dy))?;
+                    Some((wall, wall.offset(dx, dy)?))
+                })
+                .collect();
+            if options.is_empty() {
+                continue;
+            }
+            options.shuffle(&mut self.rng);
+            let (wall, _) = options
+                .iter()
+                .copied()
+                .find(|&(_, beyond)| self.is_dead_end(beyond))
+                .unwrap_or(options[0]);
+            self.grid[wall.y][wall.x] = Cell::Passage;
+            removed += 1;
+        }
+        removed
+    }
+
+    // Knocks out about `fraction` of the walls that sit in a straight line between two passages,
+    // each one adding a loop. Returns how many walls were knocked out.
+    pub fn add_loops(&mut self, fraction: f64) -> usize {
+        let mut candidates: Vec<Pos> = vec![];
+        for y in 1..self.y.saturating_sub(1) {
+            for x in 1..self.x.saturating_sub(1) {
+                if !self.grid[y][x].is_wall() {
+                    continue;
+                }
+                let open = |dx: isize, dy: isize| {
+                    let q = Pos::new(x, y).offset(dx, dy).unwrap();
+                    self.grid[q.y][q.x].is_passable()
+                };
+                // Only walls with passages on exactly two opposite sides, so rooms stay closed
+                let horizontal = open(-1, 0) && open(1, 0) && !open(0, -1) && !open(0, 1);
+                let vertical = open(0, -1) && open(0, 1) && !open(-1, 0) && !open(1, 0);
+                if horizontal || vertical {
+                    candidates.push(Pos::new(x, y));
+                }
+            }
+        }
+        candidates.shuffle(&mut self.rng);
+
+        let count = (candidates.len() as f64 * fraction.clamp(0.0, 1.0)).round() as usize;
+        for &wall in &candidates[..count] {
+            self.grid[wall.y][wall.x] = Cell::Passage;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{MazeTrait, generators::RecursiveBacktracker};
+
+    fn perfect(seed: u64) -> Maze {
+        let mut maze = Maze::with_seed(31, 21, seed);
+        maze.generate_with(&mut RecursiveBacktracker).unwrap();
+        maze
+    }
+
+    fn cells(maze: &Maze) -> Vec<Pos> {
+        (0..maze.y)
+            .flat_map(|y| (0..maze.x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| maze.grid[p.y][p.x].is_passable())
+            .collect()
+    }
+
+    // Openings between neighbours beyond the ones a tree over the same cells would have
+    fn loops(maze: &Maze) -> usize {
+        let cells = cells(maze);
+        let joins: usize = cells.iter().map(|&p| maze.open_neighbours(p).len()).sum();
+        joins / 2 + 1 - cells.len()
+    }
+
+    #[test]
+    fn full_braid_leaves_no_dead_end_it_could_open() {
+        for seed in 0..5 {
+            let mut maze = perfect(seed);
+            assert!(maze.braid(1.0) > 0);
+            for p in cells(&maze).into_iter().filter(|&p| maze.is_dead_end(p)) {
+                let stuck = DIRECTIONS.iter().all(|&d| maze.bridge(p, d).is_none());
+                assert!(stuck, "dead end at {} could still be opened", p);
+            }
+        }
+    }
+
+    #[test]
+    fn no_braid_changes_nothing() {
+        let mut maze = perfect(1);
+        let before = maze.to_string();
+        assert_eq!(maze.braid(0.0), 0);
+        assert_eq!(maze.to_string(), before);
+    }
+
+    #[test]
+    fn every_added_wall_makes_a_loop() {
+        for seed in 0..5 {
+            let mut maze = perfect(seed);
+            assert_eq!(loops(&maze), 0);
+            let added = maze.add_loops(0.5);
+            assert!(added > 0);
+            // Opening walls on both sides of a pillar closes the loop round it as well
+            assert!(loops(&maze) >= added);
+        }
+    }
+}
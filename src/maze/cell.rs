@@ -0,0 +1,132 @@
+// The byte values are the ones the grid used before it was typed, so old dumps keep loading.
+// Bytes without a meaning of their own load as `Other`, which keeps a cell to a single byte
+// at the cost of the value itself, `Other` is always written back as 255.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Cell {
+    Wall = 0,
+    Passage = 1,
+    Start = 2,
+    Path = 3,
+    Exit = 4,
+    Visited = 5,
+    Key = 6,
+    Door = 7,
+    Item = 8,
+    StairsUp = 9,
+    StairsDown = 10,
+    StairsBoth = 11,
+    Other = u8::MAX,
+}
+
+impl Cell {
+    pub fn is_wall(self) -> bool {
+        self == Cell::Wall
+    }
+
+    pub fn is_passable(self) -> bool {
+        !self.is_wall()
+    }
+
+    pub fn is_stairs(self) -> bool {
+        matches!(self, Cell::StairsUp | Cell::StairsDown | Cell::StairsBoth)
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Cell::Wall => "##",
+            Cell::Passage => "__",
+            Cell::Start => "[]",
+            Cell::Path => "..",
+            Cell::Exit => "<>",
+            Cell::Visited => ",,",
+            Cell::Key => "o-",
+            Cell::Door => "||",
+            Cell::Item => "**",
+            Cell::StairsUp => "^^",
+            Cell::StairsDown => "vv",
+            Cell::StairsBoth => "^v",
+            Cell::Other => "??",
+        }
+    }
+
+    // Reverse of `symbol`
+    pub fn from_symbol(symbol: &str) -> Option<Cell> {
+        Some(match symbol {
+            "##" => Cell::Wall,
+            "__" => Cell::Passage,
+            "[]" => Cell::Start,
+            ".." => Cell::Path,
+            "<>" => Cell::Exit,
+            ",," => Cell::Visited,
+            "o-" => Cell::Key,
+            "||" => Cell::Door,
+            "**" => Cell::Item,
+            "^^" => Cell::StairsUp,
+            "vv" => Cell::StairsDown,
+            "^v" => Cell::StairsBoth,
+            "??" => Cell::Other,
+            _ => return None,
+        })
+    }
+}
+
+impl From<u8> for Cell {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Cell::Wall,
+            1 => Cell::Passage,
+            2 => Cell::Start,
+            3 => Cell::Path,
+            4 => Cell::Exit,
+            5 => Cell::Visited,
+            6 => Cell::Key,
+            7 => Cell::Door,
+            8 => Cell::Item,
+            9 => Cell::StairsUp,
+            10 => Cell::StairsDown,
+            11 => Cell::StairsBoth,
+            _ => Cell::Other,
+        }
+    }
+}
+
+impl From<Cell> for u8 {
+    fn from(value: Cell) -> Self {
+        value as u8
+    }
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_byte_a_cell() {
+        assert_eq!(std::mem::size_of::<Cell>(), 1);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        for b in 0..=11 {
+            assert_eq!(u8::from(Cell::from(b)), b);
+        }
+        assert_eq!(Cell::from(42), Cell::Other);
+        assert_eq!(u8::from(Cell::Other), u8::MAX);
+    }
+
+    #[test]
+    fn symbols_round_trip() {
+        for b in (0..=11).chain([u8::MAX]) {
+            let cell = Cell::from(b);
+            assert_eq!(Cell::from_symbol(cell.symbol()), Some(cell));
+        }
+        assert_eq!(Cell::from_symbol("xx"), None);
+    }
+}
@@ -0,0 +1,349 @@
+use super::{Cell, Grid, Maze, OutOfBounds, Pos, Terrain};
+
+// One change to a maze, with enough kept to take it back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Set {
+        pos: Pos,
+        before: Cell,
+        after: Cell,
+    },
+    Swap {
+        a: Pos,
+        b: Pos,
+    },
+    Terrain {
+        pos: Pos,
+        before: Terrain,
+        after: Terrain,
+    },
+}
+
+impl Edit {
+    // Positions were checked when the edit was first made, so replaying it can't fail
+    fn apply(self, maze: &mut Maze) {
+        match self {
+            Edit::Set { pos, after, .. } => maze.insert_at(pos, after),
+            Edit::Swap { a, b } => maze.swap_at(a, b),
+            Edit::Terrain { pos, after, .. } => {
+                maze.set_terrain(pos, after)
+                    .expect("edits are checked when made");
+            }
+        }
+    }
+
+    fn revert(self, maze: &mut Maze) {
+        match self {
+            Edit::Set { pos, before, .. } => maze.insert_at(pos, before),
+            Edit::Swap { a, b } => maze.swap_at(a, b),
+            Edit::Terrain { pos, before, .. } => {
+                maze.set_terrain(pos, before)
+                    .expect("edits are checked when made");
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Logged {
+    Done(Vec<Edit>),
+    Undone(Vec<Edit>),
+    Redone(Vec<Edit>),
+    RolledBack(Vec<Edit>),
+}
+
+// Wraps a maze so every change goes through it and can be undone. Edits made between `begin`
+// and `commit` undo and redo as one step, and everything that happens is kept in the log.
+// Batches nest, and rolling back only takes back the innermost one.
+pub struct Editor {
+    maze: Maze,
+    undo: Vec<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+    batch: Option<Vec<Edit>>,
+    marks: Vec<usize>, // where each open `begin` started in the batch, innermost last
+    log: Vec<Logged>,
+}
+
+impl Editor {
+    pub fn new(maze: Maze) -> Self {
+        Self {
+            maze,
+            undo: vec![],
+            redo: vec![],
+            batch: None,
+            marks: vec![],
+            log: vec![],
+        }
+    }
+
+    pub fn maze(&self) -> &Maze {
+        &self.maze
+    }
+
+    // Drops the history, an open batch is kept as it is
+    pub fn into_maze(self) -> Maze {
+        self.maze
+    }
+
+    pub fn log(&self) -> &[Logged] {
+        &self.log
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.batch.is_none() && !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.batch.is_none() && !self.redo.is_empty()
+    }
+
+    // Returns the cell that was replaced
+    pub fn set(&mut self, pos: Pos, cell: Cell) -> Result<Cell, OutOfBounds> {
+        let before = self.maze.try_set(pos, cell)?;
+        self.record(Edit::Set {
+            pos,
+            before,
+            after: cell,
+        });
+        Ok(before)
+    }
+
+    pub fn swap(&mut self, a: Pos, b: Pos) -> Result<(), OutOfBounds> {
+        self.maze.try_swap(a, b)?;
+        self.record(Edit::Swap { a, b });
+        Ok(())
+    }
+
+    // Returns the terrain that was replaced
+    pub fn set_terrain(&mut self, pos: Pos, terrain: Terrain) -> Result<Terrain, OutOfBounds> {
+        let before = self.maze.set_terrain(pos, terrain)?;
+        self.record(Edit::Terrain {
+            pos,
+            before,
+            after: terrain,
+        });
+        Ok(before)
+    }
+
+    // A batch only replaces what there was to redo once it commits, so one that is rolled
+    // back leaves redo as it was
+    fn record(&mut self, edit: Edit) {
+        match &mut self.batch {
+            Some(batch) => batch.push(edit),
+            None => {
+                self.redo.clear();
+                self.undo.push(vec![edit]);
+                self.log.push(Logged::Done(vec![edit]));
+            }
+        }
+    }
+
+    pub fn begin(&mut self) {
+        let batch = self.batch.get_or_insert_with(Vec::new);
+        self.marks.push(batch.len());
+    }
+
+    // Only the outermost `commit` closes the batch
+    pub fn commit(&mut self) {
+        if self.marks.pop().is_none() || !self.marks.is_empty() {
+            return;
+        }
+        if let Some(batch) = self.batch.take()
+            && !batch.is_empty()
+        {
+            self.redo.clear();
+            self.log.push(Logged::Done(batch.clone()));
+            self.undo.push(batch);
+        }
+    }
+
+    // Takes back everything since the innermost `begin`, the batches around it stay open
+    pub fn rollback(&mut self) {
+        let (Some(mark), Some(batch)) = (self.marks.pop(), &mut self.batch) else {
+            return;
+        };
+        let undone = batch.split_off(mark);
+        for edit in undone.iter().rev() {
+            edit.revert(&mut self.maze);
+        }
+        if !undone.is_empty() {
+            self.log.push(Logged::RolledBack(undone));
+        }
+        if self.marks.is_empty() {
+            self.batch = None;
+        }
+    }
+
+    // Runs `f` as one batch, rolled back if it fails. Inside another transaction only this
+    // batch is taken back, and the outer one carries on.
+    pub fn transaction<T, E>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, E>) -> Result<T, E> {
+        self.begin();
+        match f(self) {
+            Ok(value) => {
+                self.commit();
+                Ok(value)
+            }
+            Err(e) => {
+                self.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    // Nothing to undo while a batch is open, commit or roll it back first
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+        let Some(edits) = self.undo.pop() else {
+            return false;
+        };
+        for edit in edits.iter().rev() {
+            edit.revert(&mut self.maze);
+        }
+        self.log.push(Logged::Undone(edits.clone()));
+        self.redo.push(edits);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if !self.can_redo() {
+            return false;
+        }
+        let Some(edits) = self.redo.pop() else {
+            return false;
+        };
+        for edit in &edits {
+            edit.apply(&mut self.maze);
+        }
+        self.log.push(Logged::Redone(edits.clone()));
+        self.undo.push(edits);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::MazeTrait;
+
+    fn editor() -> Editor {
+        Editor::new(Maze::with_seed(11, 9, 1))
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut ed = editor();
+        let (a, b) = (Pos::new(1, 1), Pos::new(3, 1));
+        assert_eq!(ed.set(a, Cell::Passage), Ok(Cell::Wall));
+        ed.set(b, Cell::Key).unwrap();
+        ed.swap(a, b).unwrap();
+        assert_eq!(
+            (ed.maze().get_at(a), ed.maze().get_at(b)),
+            (Cell::Key, Cell::Passage)
+        );
+
+        assert!(ed.undo());
+        assert_eq!(
+            (ed.maze().get_at(a), ed.maze().get_at(b)),
+            (Cell::Passage, Cell::Key)
+        );
+        assert!(ed.undo() && ed.undo());
+        assert_eq!(
+            (ed.maze().get_at(a), ed.maze().get_at(b)),
+            (Cell::Wall, Cell::Wall)
+        );
+        assert!(!ed.undo());
+
+        assert!(ed.redo() && ed.redo());
+        assert_eq!(
+            (ed.maze().get_at(a), ed.maze().get_at(b)),
+            (Cell::Passage, Cell::Key)
+        );
+        ed.set(a, Cell::Item).unwrap();
+        assert!(!ed.can_redo());
+        assert!(ed.set(Pos::new(11, 0), Cell::Passage).is_err());
+    }
+
+    #[test]
+    fn batches_undo_as_one() {
+        let mut ed = editor();
+        ed.begin();
+        ed.set(Pos::new(1, 1), Cell::Passage).unwrap();
+        ed.begin();
+        ed.set(Pos::new(2, 1), Cell::Passage).unwrap();
+        ed.commit();
+        assert!(!ed.can_undo());
+        ed.commit();
+        assert!(ed.undo());
+        assert_eq!(ed.maze().get_at(Pos::new(1, 1)), Cell::Wall);
+        assert_eq!(ed.maze().get_at(Pos::new(2, 1)), Cell::Wall);
+        assert!(!ed.can_undo());
+    }
+
+    #[test]
+    fn failed_inner_transaction_only_rolls_back_itself() {
+        let mut ed = editor();
+        let (a, b, c) = (Pos::new(1, 1), Pos::new(2, 1), Pos::new(3, 1));
+        let outer: Result<(), OutOfBounds> = ed.transaction(|ed| {
+            ed.set(a, Cell::Passage)?;
+            let inner = ed.transaction(|ed| {
+                ed.set(b, Cell::Passage)?;
+                ed.set(Pos::new(99, 99), Cell::Passage)
+            });
+            assert!(inner.is_err());
+            ed.set(c, Cell::Key)?;
+            Ok(())
+        });
+        assert!(outer.is_ok());
+        let cells = |ed: &Editor| [a, b, c].map(|p| ed.maze().get_at(p));
+        assert_eq!(cells(&ed), [Cell::Passage, Cell::Wall, Cell::Key]);
+
+        // Everything the outer transaction kept undoes in one step
+        assert!(ed.undo());
+        assert_eq!(cells(&ed), [Cell::Wall; 3]);
+        assert!(!ed.can_undo());
+    }
+
+    #[test]
+    fn failure_passed_up_rolls_back_everything() {
+        let mut ed = editor();
+        let outer = ed.transaction(|ed| {
+            ed.set(Pos::new(1, 1), Cell::Passage)?;
+            ed.transaction(|ed| ed.set(Pos::new(99, 99), Cell::Passage))?;
+            ed.set(Pos::new(3, 1), Cell::Key)
+        });
+        assert!(outer.is_err());
+        assert_eq!(ed.maze().get_at(Pos::new(1, 1)), Cell::Wall);
+        assert_eq!(ed.maze().get_at(Pos::new(3, 1)), Cell::Wall);
+        assert!(!ed.can_undo());
+        assert!(matches!(ed.log().last(), Some(Logged::RolledBack(_))));
+    }
+
+    #[test]
+    fn only_committed_batches_drop_redo() {
+        let mut ed = editor();
+        let a = Pos::new(1, 1);
+        ed.set(a, Cell::Passage).unwrap();
+        assert!(ed.undo());
+
+        let failed: Result<(), OutOfBounds> = ed.transaction(|ed| {
+            ed.set(Pos::new(3, 1), Cell::Key)?;
+            ed.set(Pos::new(99, 99), Cell::Key)?;
+            Ok(())
+        });
+        assert!(failed.is_err());
+        assert!(ed.can_redo());
+
+        ed.begin();
+        ed.commit();
+        assert!(ed.can_redo());
+
+        ed.begin();
+        ed.set(Pos::new(3, 1), Cell::Key).unwrap();
+        ed.commit();
+        assert!(!ed.can_redo());
+        assert!(!ed.redo());
+        assert_eq!(ed.maze().get_at(a), Cell::Wall);
+    }
+}
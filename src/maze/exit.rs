@@ -0,0 +1,129 @@
+use super::{Cell, Maze, Pos};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExitPlacement {
+    #[default]
+    None,
+    // The reachable cell furthest from the start
+    Farthest,
+    // Both ends of the longest path in the maze, the start moves to one of them
+    Diameter,
+    // An opening in the outer wall, next to the border cell furthest from the start
+    Boundary,
+}
+
+impl Maze {
+    pub fn exit_placement(&self) -> ExitPlacement {
+        self.exit_placement
+    }
+
+    // Takes effect on the next generate
+    pub fn set_exit_placement(&mut self, placement: ExitPlacement) {
+        self.exit_placement = placement;
+    }
+
+    pub(super) fn place_exit(&mut self) {
+        self.exit = None;
+        let exit = match self.exit_placement {
+            ExitPlacement::None => return,
+            ExitPlacement::Farthest => self.farthest(self.start).0,
+            ExitPlacement::Diameter => {
+                let (a, _) = self.farthest(self.start);
+                let (b, _) = self.farthest(a);
+                self.grid[self.start.y][self.start.x] = Cell::Passage;
+                self.start = a;
+                self.grid[a.y][a.x] = Cell::Start;
+                b
+            }
+            // A wrapping maze has no outer wall to open
+            ExitPlacement::Boundary if self.wrap => self.farthest(self.start).0,
+            ExitPlacement::Boundary => match self.boundary_exit() {
+                Some(pos) => pos,
+                None => return,
+            },
+        };
+        if exit != self.start {
+            self.grid[exit.y][exit.x] = Cell::Exit;
+            self.exit = Some(exit);
+        }
+    }
+
+    // Border cell next to the passage that is furthest from the start
+    fn boundary_exit(&self) -> Option<Pos> {
+        let dist = self.distances(self.start);
+        let on_border = |p: Pos| p.x == 0 || p.y == 0 || p.x == self.x - 1 || p.y == self.y - 1;
+        let is_corner = |p: Pos| (p.x == 0 || p.x == self.x - 1) && (p.y == 0 || p.y == self.y - 1);
+
+        let mut best: Option<(usize, Pos)> = None;
+        for (y, row) in dist.iter().enumerate() {
+            for (x, d) in row.iter().enumerate() {
+                let Some(d) = *d else { continue };
+                let cell = Pos::new(x, y);
+                if on_border(cell) || best.is_some_and(|(bd, _)| bd >= d) {
+                    continue;
+                }
+                let door = [(0, 1), (1, 0), (0, -1), (-1, 0)]
+                    .iter()
+                    .filter_map(|&(dx, dy)| cell.offset(dx, dy))
+                    .find(|&p| on_border(p) && !is_corner(p));
+                if let Some(door) = door {
+                    best = Some((d, door));
+                }
+            }
+        }
+        best.map(|(_, door)| door)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::MazeTrait;
+
+    fn placed(placement: ExitPlacement, seed: u64) -> Maze {
+        let mut maze = Maze::with_seed(21, 15, seed);
+        maze.set_exit_placement(placement);
+        maze.generate();
+        maze
+    }
+
+    #[test]
+    fn farthest_exit_is_as_far_as_it_gets() {
+        for seed in 0..5 {
+            let maze = placed(ExitPlacement::Farthest, seed);
+            let exit = maze.exit().unwrap();
+            assert_eq!(maze.grid[exit.y][exit.x], Cell::Exit);
+            let dist = maze.distances(maze.start);
+            let most = dist.iter().flatten().flatten().max().copied();
+            assert_eq!(dist[exit.y][exit.x], most);
+        }
+    }
+
+    #[test]
+    fn diameter_moves_the_start_to_one_end() {
+        for seed in 0..5 {
+            let maze = placed(ExitPlacement::Diameter, seed);
+            let exit = maze.exit().unwrap();
+            assert_eq!(maze.grid[maze.start.y][maze.start.x], Cell::Start);
+            // Nothing is further from either end than the other end
+            let length = maze.distances(maze.start)[exit.y][exit.x].unwrap();
+            assert_eq!(maze.farthest(maze.start).1, length);
+            assert_eq!(maze.farthest(exit).1, length);
+        }
+    }
+
+    #[test]
+    fn boundary_exit_opens_the_outer_wall() {
+        for seed in 0..5 {
+            let maze = placed(ExitPlacement::Boundary, seed);
+            let exit = maze.exit().unwrap();
+            assert!(exit.x == 0 || exit.y == 0 || exit.x == 20 || exit.y == 14);
+            assert!(maze.distances(maze.start)[exit.y][exit.x].is_some());
+        }
+    }
+
+    #[test]
+    fn no_exit_by_default() {
+        assert_eq!(placed(ExitPlacement::None, 1).exit(), None);
+    }
+}
@@ -0,0 +1,1202 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+};
+
+use rand::{Rng as _, seq::SliceRandom};
+
+use super::{Cell, Grid, Maze, Pos, rows::EllerRows, util::Sets};
+
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+// Every generator fills the same grid. Apart from Prim's, which works directly on grid
+// cells, they carve on a lattice where cell (cx, cy) lives at grid (2cx + 1, 2cy + 1) and
+// the wall between two neighbouring cells is the grid cell halfway between them.
+pub trait Generator {
+    fn name(&self) -> &'static str;
+
+    fn carve(&mut self, maze: &mut Maze);
+
+    // Row by row generators can't follow the outline of a mask
+    fn supports_mask(&self) -> bool {
+        true
+    }
+
+    // Nor can they join the last row or column back onto the first
+    fn supports_wrap(&self) -> bool {
+        true
+    }
+}
+
+// A generator asked for a maze shape it can't carve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerateError {
+    Mask(&'static str),
+    Wrap(&'static str),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::Mask(name) => write!(f, "{} can't generate inside a mask", name),
+            GenerateError::Wrap(name) => write!(f, "{} can't generate a wrapping maze", name),
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+// One of each generator with its default settings, and every selection of the growing tree
+pub fn all() -> Vec<Box<dyn Generator>> {
+    vec![
+        Box::new(Prim),
+        Box::new(RecursiveBacktracker),
+        Box::new(Kruskal),
+        Box::new(Eller),
+        Box::new(Sidewinder),
+        Box::new(BinaryTree),
+        Box::new(HuntAndKill),
+        Box::new(GrowingTree::new(Selection::Newest)),
+        Box::new(GrowingTree::new(Selection::Random)),
+        Box::new(GrowingTree::new(Selection::Oldest)),
+        Box::new(Wilson),
+        Box::new(AldousBroder),
+        Box::new(AldousBroderWilson::default()),
+        Box::new(RecursiveDivision::default()),
+        Box::new(Dungeon::default()),
+        Box::new(Cave::default()),
+    ]
+}
+
+fn cells(maze: &Maze) -> (usize, usize) {
+    ((maze.x - 1) / 2, (maze.y - 1) / 2)
+}
+
+fn start_cell(maze: &Maze) -> (usize, usize) {
+    ((maze.start.x - 1) / 2, (maze.start.y - 1) / 2)
+}
+
+fn allowed(maze: &Maze, c: (usize, usize)) -> bool {
+    maze.in_mask(Pos::new(c.0 * 2 + 1, c.1 * 2 + 1))
+}
+
+fn is_open(maze: &Maze, c: (usize, usize)) -> bool {
+    maze.grid[c.1 * 2 + 1][c.0 * 2 + 1].is_passable()
+}
+
+fn open(maze: &mut Maze, c: (usize, usize)) {
+    maze.grid[c.1 * 2 + 1][c.0 * 2 + 1] = Cell::Passage;
+}
+
+// Opens both cells and the wall between them. Cells at opposite ends of a wrapping maze
+// meet across the outer ring.
+fn link(maze: &mut Maze, a: (usize, usize), b: (usize, usize)) {
+    open(maze, a);
+    open(maze, b);
+    let wall = |a: usize, b: usize| if a.abs_diff(b) > 1 { 0 } else { a + b + 1 };
+    maze.grid[wall(a.1, b.1)][wall(a.0, b.0)] = Cell::Passage;
+}
+
+fn neighbours(maze: &Maze, c: (usize, usize)) -> Vec<(usize, usize)> {
+    let (w, h) = cells(maze);
+    DIRECTIONS
+        .iter()
+        .filter_map(|&(dx, dy)| {
+            let (nx, ny) = if maze.wrap {
+                (
+                    (c.0 + w).wrapping_add_signed(dx) % w,
+                    (c.1 + h).wrapping_add_signed(dy) % h,
+                )
+            } else {
+                (c.0.checked_add_signed(dx)?, c.1.checked_add_signed(dy)?)
+            };
+            (nx < w && ny < h && (nx, ny) != c && allowed(maze, (nx, ny))).then_some((nx, ny))
+        })
+        .collect()
+}
+
+// Grid cells next to `pos` that Prim's may carve. The outer ring stays wall unless the maze wraps.
+fn prim_neighbours(maze: &Maze, pos: Pos) -> Vec<Pos> {
+    DIRECTIONS
+        .iter()
+        .filter_map(|&(dx, dy)| maze.neighbour(pos, dx, dy))
+        .filter(|&n| {
+            (maze.wrap || (n.x > 0 && n.y > 0 && n.x < maze.x - 1 && n.y < maze.y - 1))
+                && maze.in_mask(n)
+        })
+        .collect()
+}
+
+// Cells connected to the start through the mask, in the order they were reached
+fn region(maze: &Maze) -> Vec<(usize, usize)> {
+    let (w, _) = cells(maze);
+    let start = start_cell(maze);
+    let mut seen = HashSet::from([start.1 * w + start.0]);
+    let mut found = vec![start];
+    let mut i = 0;
+    while i < found.len() {
+        for n in neighbours(maze, found[i]) {
+            if seen.insert(n.1 * w + n.0) {
+                found.push(n);
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+fn unvisited_neighbours(maze: &Maze, c: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut found = neighbours(maze, c);
+    found.retain(|&n| !is_open(maze, n));
+    found
+}
+
+/// Randomized Prim's over a frontier of wall cells.
+pub struct Prim;
+
+impl Generator for Prim {
+    fn name(&self) -> &'static str {
+        "prim"
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        Self::begin(maze);
+        while Self::step(maze).is_some() {}
+    }
+}
+
+impl Prim {
+    // Opens the start and puts the walls around it on the frontier
+    pub(super) fn begin(maze: &mut Maze) {
+        let start = maze.start;
+        maze.grid[start.y][start.x] = Cell::Passage;
+        for n in prim_neighbours(maze, start) {
+            maze.walls.push((n.x, n.y));
+        }
+    }
+
+    // Carves the next wall off the frontier, None once the frontier has run out
+    pub(super) fn step(maze: &mut Maze) -> Option<Pos> {
+        while !maze.walls.is_empty() {
+            // Select a random wall from the list
+            let idx = maze.rng.random_range(0..maze.walls.len());
+            let (wx, wy) = maze.walls.swap_remove(idx);
+            let wall = Pos::new(wx, wy);
+
+            // Count how many adjacent cells are passages
+            let adjacent = DIRECTIONS
+                .iter()
+                .filter_map(|&(dx, dy)| maze.neighbour(wall, dx, dy))
+                .filter(|n| maze.grid[n.y][n.x] == Cell::Passage)
+                .count();
+
+            // If the wall divides one passage cell from an unvisited cell, convert it
+            if adjacent == 1 {
+                maze.grid[wy][wx] = Cell::Passage;
+
+                // Add neighboring walls of the newly made passage cell
+                for n in prim_neighbours(maze, wall) {
+                    if maze.grid[n.y][n.x] == Cell::Wall {
+                        maze.walls.push((n.x, n.y));
+                    }
+                }
+                return Some(wall);
+            }
+        }
+        None
+    }
+}
+
+/// Depth-first search with an explicit stack, long winding corridors.
+pub struct RecursiveBacktracker;
+
+impl Generator for RecursiveBacktracker {
+    fn name(&self) -> &'static str {
+        "backtracker"
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let start = start_cell(maze);
+        open(maze, start);
+        let mut stack = vec![start];
+
+        while let Some(&current) = stack.last() {
+            let next = unvisited_neighbours(maze, current);
+            if next.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let n = next[maze.rng.random_range(0..next.len())];
+            link(maze, current, n);
+            stack.push(n);
+        }
+    }
+}
+
+/// Randomized Kruskal's, joining disjoint sets across shuffled walls.
+pub struct Kruskal;
+
+impl Generator for Kruskal {
+    fn name(&self) -> &'static str {
+        "kruskal"
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let (w, h) = cells(maze);
+        let mut edges = vec![];
+        for (cx, cy) in region(maze) {
+            open(maze, (cx, cy));
+            let (east, south) = ((cx + 1) % w, (cy + 1) % h);
+            if (cx + 1 < w || maze.wrap && w > 2) && allowed(maze, (east, cy)) {
+                edges.push(((cx, cy), (east, cy)));
+            }
+            if (cy + 1 < h || maze.wrap && h > 2) && allowed(maze, (cx, south)) {
+                edges.push(((cx, cy), (cx, south)));
+            }
+        }
+        edges.shuffle(&mut maze.rng);
+
+        let mut sets = Sets::new(w * h);
+        for (a, b) in edges {
+            if sets.union(a.1 * w + a.0, b.1 * w + b.0) {
+                link(maze, a, b);
+            }
+        }
+    }
+}
+
+/// Eller's, one row of sets at a time.
+pub struct Eller;
+
+impl Generator for Eller {
+    fn name(&self) -> &'static str {
+        "eller"
+    }
+
+    fn supports_mask(&self) -> bool {
+        false
+    }
+
+    fn supports_wrap(&self) -> bool {
+        false
+    }
+
+    // The rows come from `EllerRows`, drawing on the maze's own rng
+    fn carve(&mut self, maze: &mut Maze) {
+        let (w, h) = cells(maze);
+        let rows = EllerRows::with_rng(w, h, &mut maze.rng);
+        for (cy, row) in rows.enumerate() {
+            let gy = cy * 2 + 1;
+            for cx in 0..w {
+                let gx = cx * 2 + 1;
+                maze.grid[gy][gx] = Cell::Passage;
+                if row.east[cx] {
+                    maze.grid[gy][gx + 1] = Cell::Passage;
+                }
+                if row.south[cx] {
+                    maze.grid[gy + 1][gx] = Cell::Passage;
+                }
+            }
+        }
+    }
+}
+
+/// Sidewinder, runs along each row that each open one passage north.
+pub struct Sidewinder;
+
+impl Generator for Sidewinder {
+    fn name(&self) -> &'static str {
+        "sidewinder"
+    }
+
+    fn supports_mask(&self) -> bool {
+        false
+    }
+
+    fn supports_wrap(&self) -> bool {
+        false
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let (w, h) = cells(maze);
+        for cy in 0..h {
+            let mut run_start = 0;
+            for cx in 0..w {
+                open(maze, (cx, cy));
+                let at_east = cx + 1 == w;
+                if cy == 0 {
+                    if !at_east {
+                        link(maze, (cx, cy), (cx + 1, cy));
+                    }
+                } else if at_east || maze.rng.random_bool(0.5) {
+                    let up = maze.rng.random_range(run_start..=cx);
+                    link(maze, (up, cy), (up, cy - 1));
+                    run_start = cx + 1;
+                } else {
+                    link(maze, (cx, cy), (cx + 1, cy));
+                }
+            }
+        }
+    }
+}
+
+/// Binary tree, each cell opens either north or east.
+pub struct BinaryTree;
+
+impl Generator for BinaryTree {
+    fn name(&self) -> &'static str {
+        "binary-tree"
+    }
+
+    fn supports_mask(&self) -> bool {
+        false
+    }
+
+    fn supports_wrap(&self) -> bool {
+        false
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let (w, h) = cells(maze);
+        for cy in 0..h {
+            for cx in 0..w {
+                open(maze, (cx, cy));
+                let mut options = vec![];
+                if cy > 0 {
+                    options.push((cx, cy - 1));
+                }
+                if cx + 1 < w {
+                    options.push((cx + 1, cy));
+                }
+                if !options.is_empty() {
+                    let n = options[maze.rng.random_range(0..options.len())];
+                    link(maze, (cx, cy), n);
+                }
+            }
+        }
+    }
+}
+
+/// Hunt-and-kill, random walks restarted from the first unvisited cell next to the maze.
+pub struct HuntAndKill;
+
+impl Generator for HuntAndKill {
+    fn name(&self) -> &'static str {
+        "hunt-and-kill"
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let (w, h) = cells(maze);
+        let mut current = start_cell(maze);
+        open(maze, current);
+
+        loop {
+            let next = unvisited_neighbours(maze, current);
+            if !next.is_empty() {
+                let n = next[maze.rng.random_range(0..next.len())];
+                link(maze, current, n);
+                current = n;
+                continue;
+            }
+
+            // Hunt for an unvisited cell bordering the carved area
+            let mut found = None;
+            'hunt: for cy in 0..h {
+                for cx in 0..w {
+                    if is_open(maze, (cx, cy)) || !allowed(maze, (cx, cy)) {
+                        continue;
+                    }
+                    let mut visited = neighbours(maze, (cx, cy));
+                    visited.retain(|&n| is_open(maze, n));
+                    if !visited.is_empty() {
+                        found = Some(((cx, cy), visited));
+                        break 'hunt;
+                    }
+                }
+            }
+
+            match found {
+                Some((cell, visited)) => {
+                    let n = visited[maze.rng.random_range(0..visited.len())];
+                    link(maze, cell, n);
+                    current = cell;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    Newest,
+    Random,
+    Oldest,
+}
+
+/// Growing tree, the selection decides between backtracker-like and Prim-like textures.
+pub struct GrowingTree {
+    pub selection: Selection,
+}
+
+impl GrowingTree {
+    pub fn new(selection: Selection) -> Self {
+        Self { selection }
+    }
+}
+
+impl Generator for GrowingTree {
+    fn name(&self) -> &'static str {
+        match self.selection {
+            Selection::Newest => "growing-tree-newest",
+            Selection::Random => "growing-tree-random",
+            Selection::Oldest => "growing-tree-oldest",
+        }
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let start = start_cell(maze);
+        open(maze, start);
+        let mut active = vec![start];
+
+        while !active.is_empty() {
+            let idx = match self.selection {
+                Selection::Newest => active.len() - 1,
+                Selection::Random => maze.rng.random_range(0..active.len()),
+                Selection::Oldest => 0,
+            };
+            let next = unvisited_neighbours(maze, active[idx]);
+            if next.is_empty() {
+                active.remove(idx);
+                continue;
+            }
+            let n = next[maze.rng.random_range(0..next.len())];
+            link(maze, active[idx], n);
+            active.push(n);
+        }
+    }
+}
+
+// Loop-erased random walks from every unvisited cell until they hit the carved tree.
+// Needs at least one open cell to walk towards.
+fn wilson_walks(maze: &mut Maze) {
+    let (w, h) = cells(maze);
+    let mut unvisited = region(maze);
+    unvisited.retain(|&c| !is_open(maze, c));
+    let mut exits: Vec<Option<(usize, usize)>> = vec![None; w * h];
+
+    while !unvisited.is_empty() {
+        let first = unvisited[maze.rng.random_range(0..unvisited.len())];
+
+        // Remembering only the last exit taken from each cell erases any loops in the walk
+        let mut current = first;
+        while !is_open(maze, current) {
+            let next = neighbours(maze, current);
+            let n = next[maze.rng.random_range(0..next.len())];
+            exits[current.1 * w + current.0] = Some(n);
+            current = n;
+        }
+
+        let mut path = vec![first];
+        while !is_open(maze, path[path.len() - 1]) {
+            let last = path[path.len() - 1];
+            path.push(exits[last.1 * w + last.0].unwrap());
+        }
+        for pair in path.windows(2) {
+            link(maze, pair[0], pair[1]);
+        }
+        unvisited.retain(|&c| !is_open(maze, c));
+    }
+}
+
+// Random walk that links every cell it enters for the first time, until `target` cells are open
+fn aldous_broder_walk(maze: &mut Maze, target: usize) {
+    let mut current = start_cell(maze);
+    open(maze, current);
+    let mut visited = 1;
+
+    while visited < target {
+        let next = neighbours(maze, current);
+        let n = next[maze.rng.random_range(0..next.len())];
+        if !is_open(maze, n) {
+            link(maze, current, n);
+            visited += 1;
+        }
+        current = n;
+    }
+}
+
+/// Wilson's, a uniform spanning tree built from loop-erased random walks.
+pub struct Wilson;
+
+impl Generator for Wilson {
+    fn name(&self) -> &'static str {
+        "wilson"
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        open(maze, start_cell(maze));
+        wilson_walks(maze);
+    }
+}
+
+/// Aldous-Broder, a uniform spanning tree from one unbiased random walk.
+pub struct AldousBroder;
+
+impl Generator for AldousBroder {
+    fn name(&self) -> &'static str {
+        "aldous-broder"
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let target = region(maze).len();
+        aldous_broder_walk(maze, target);
+    }
+}
+
+/// Aldous-Broder while the maze is sparse, then Wilson's once the walks have something to hit.
+/// Both halves are unbiased, so the result is still a uniform spanning tree.
+pub struct AldousBroderWilson {
+    pub switch_at: f64, // fraction of cells visited before switching to Wilson's
+}
+
+impl AldousBroderWilson {
+    pub fn new(switch_at: f64) -> Self {
+        Self { switch_at }
+    }
+}
+
+impl Default for AldousBroderWilson {
+    fn default() -> Self {
+        Self::new(1.0 / 3.0)
+    }
+}
+
+impl Generator for AldousBroderWilson {
+    fn name(&self) -> &'static str {
+        "aldous-broder-wilson"
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let cells = region(maze).len();
+        let target = (cells as f64 * self.switch_at.clamp(0.0, 1.0)).ceil() as usize;
+        aldous_broder_walk(maze, target.max(1));
+        wilson_walks(maze);
+    }
+}
+
+/// Recursive division, the only wall-adding generator. It opens the whole lattice into one
+/// room and splits it with walls that each leave a single gap.
+pub struct RecursiveDivision {
+    pub min_chamber: usize, // chambers are never split into parts narrower than this, in cells
+    pub stop_chance: f64,   // chance to leave a splittable chamber as an open room
+}
+
+impl RecursiveDivision {
+    pub fn new(min_chamber: usize, stop_chance: f64) -> Self {
+        Self {
+            min_chamber: min_chamber.max(1),
+            stop_chance,
+        }
+    }
+}
+
+impl Default for RecursiveDivision {
+    fn default() -> Self {
+        Self::new(1, 0.0)
+    }
+}
+
+impl Generator for RecursiveDivision {
+    fn name(&self) -> &'static str {
+        "recursive-division"
+    }
+
+    fn supports_mask(&self) -> bool {
+        false
+    }
+
+    fn supports_wrap(&self) -> bool {
+        false
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let (w, h) = cells(maze);
+        for gy in 1..h * 2 {
+            for gx in 1..w * 2 {
+                maze.grid[gy][gx] = Cell::Passage;
+            }
+        }
+
+        // Chambers as (x, y, width, height) in cells
+        let min = self.min_chamber.max(1);
+        let mut chambers = vec![(0, 0, w, h)];
+        while let Some((cx, cy, cw, ch)) = chambers.pop() {
+            let split_x = cw >= min * 2;
+            let split_y = ch >= min * 2;
+            if !split_x && !split_y {
+                continue;
+            }
+            if maze.rng.random_bool(self.stop_chance.clamp(0.0, 1.0)) {
+                continue;
+            }
+
+            let vertical = match (split_x, split_y) {
+                (true, false) => true,
+                (false, true) => false,
+                _ if cw != ch => cw > ch,
+                _ => maze.rng.random_bool(0.5),
+            };
+
+            if vertical {
+                let k = maze.rng.random_range(min..=cw - min);
+                let gx = (cx + k) * 2;
+                for gy in cy * 2 + 1..(cy + ch) * 2 {
+                    maze.grid[gy][gx] = Cell::Wall;
+                }
+                let gap = maze.rng.random_range(cy..cy + ch);
+                maze.grid[gap * 2 + 1][gx] = Cell::Passage;
+                chambers.push((cx, cy, k, ch));
+                chambers.push((cx + k, cy, cw - k, ch));
+            } else {
+                let k = maze.rng.random_range(min..=ch - min);
+                let gy = (cy + k) * 2;
+                for gx in cx * 2 + 1..(cx + cw) * 2 {
+                    maze.grid[gy][gx] = Cell::Wall;
+                }
+                let gap = maze.rng.random_range(cx..cx + cw);
+                maze.grid[gy][gap * 2 + 1] = Cell::Passage;
+                chambers.push((cx, cy, cw, k));
+                chambers.push((cx, cy + k, cw, ch - k));
+            }
+        }
+    }
+}
+
+/// Rooms and corridors. Drops rooms wherever they fit, fills the rock between them with maze
+/// corridors, knocks doors through until everything is joined up and then fills the dead ends
+/// back in, so what's left are corridors running from room to room.
+pub struct Dungeon {
+    pub room_attempts: usize,
+    pub room_size: (usize, usize), // smallest and largest side of a room, in cells
+    pub extra_doors: f64,          // chance for each leftover connection to become a door anyway
+    pub remove_dead_ends: bool,
+}
+
+impl Dungeon {
+    pub fn new(room_attempts: usize, min_room: usize, max_room: usize) -> Self {
+        // A single cell room with one door would be filled in as a dead end
+        let min_room = min_room.max(2);
+        Self {
+            room_attempts,
+            room_size: (min_room, max_room.max(min_room)),
+            extra_doors: 0.05,
+            remove_dead_ends: true,
+        }
+    }
+}
+
+impl Default for Dungeon {
+    fn default() -> Self {
+        Self::new(40, 2, 5)
+    }
+}
+
+impl Generator for Dungeon {
+    fn name(&self) -> &'static str {
+        "dungeon"
+    }
+
+    fn supports_wrap(&self) -> bool {
+        false
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let (w, h) = cells(maze);
+        let (min, max) = self.room_size;
+        let mut in_room = vec![false; w * h];
+        let mut rooms: Vec<(usize, usize, usize, usize)> = vec![];
+
+        for _ in 0..self.room_attempts {
+            let (rw, rh) = (
+                maze.rng.random_range(min..=max),
+                maze.rng.random_range(min..=max),
+            );
+            if rw > w || rh > h {
+                continue;
+            }
+            let (rx, ry) = (
+                maze.rng.random_range(0..=w - rw),
+                maze.rng.random_range(0..=h - rh),
+            );
+            // Keep a cell of rock around each room for the corridors to run through
+            let clear = rooms.iter().all(|&(ox, oy, ow, oh)| {
+                rx > ox + ow || ox > rx + rw || ry > oy + oh || oy > ry + rh
+            });
+            let inside = (ry..ry + rh).all(|cy| (rx..rx + rw).all(|cx| allowed(maze, (cx, cy))));
+            if !clear || !inside {
+                continue;
+            }
+            for gy in ry * 2 + 1..(ry + rh) * 2 {
+                for gx in rx * 2 + 1..(rx + rw) * 2 {
+                    maze.grid[gy][gx] = Cell::Passage;
+                }
+            }
+            for cy in ry..ry + rh {
+                for cx in rx..rx + rw {
+                    in_room[cy * w + cx] = true;
+                }
+            }
+            rooms.push((rx, ry, rw, rh));
+        }
+        if let Some(&(rx, ry, rw, rh)) = rooms.first() {
+            maze.start = Pos::new((rx + rw / 2) * 2 + 1, (ry + rh / 2) * 2 + 1);
+        }
+
+        // Every bit of rock left over becomes a maze of its own
+        for cy in 0..h {
+            for cx in 0..w {
+                if is_open(maze, (cx, cy)) || !allowed(maze, (cx, cy)) {
+                    continue;
+                }
+                open(maze, (cx, cy));
+                let mut stack = vec![(cx, cy)];
+                while let Some(&current) = stack.last() {
+                    let next = unvisited_neighbours(maze, current);
+                    if next.is_empty() {
+                        stack.pop();
+                        continue;
+                    }
+                    let n = next[maze.rng.random_range(0..next.len())];
+                    link(maze, current, n);
+                    stack.push(n);
+                }
+            }
+        }
+
+        // Label the rooms and corridor mazes, then join them across shuffled walls like Kruskal's
+        let wall = |a: (usize, usize), b: (usize, usize)| Pos::new(a.0 + b.0 + 1, a.1 + b.1 + 1);
+        let mut label = vec![usize::MAX; w * h];
+        let mut regions = 0;
+        for c in (0..h).flat_map(|cy| (0..w).map(move |cx| (cx, cy))) {
+            if label[c.1 * w + c.0] != usize::MAX || !is_open(maze, c) {
+                continue;
+            }
+            label[c.1 * w + c.0] = regions;
+            let mut stack = vec![c];
+            while let Some(cell) = stack.pop() {
+                for n in neighbours(maze, cell) {
+                    let between = wall(cell, n);
+                    if label[n.1 * w + n.0] == usize::MAX
+                        && maze.grid[between.y][between.x].is_passable()
+                    {
+                        label[n.1 * w + n.0] = regions;
+                        stack.push(n);
+                    }
+                }
+            }
+            regions += 1;
+        }
+
+        let mut connectors = vec![];
+        for (cx, cy) in (0..h).flat_map(|cy| (0..w).map(move |cx| (cx, cy))) {
+            for n in [(cx + 1, cy), (cx, cy + 1)] {
+                if n.0 < w && n.1 < h && is_open(maze, (cx, cy)) && is_open(maze, n) {
+                    let between = wall((cx, cy), n);
+                    if maze.grid[between.y][between.x].is_wall() {
+                        connectors.push(((cx, cy), n));
+                    }
+                }
+            }
+        }
+        connectors.shuffle(&mut maze.rng);
+
+        let mut sets = Sets::new(regions);
+        for (a, b) in connectors {
+            let joined = sets.union(label[a.1 * w + a.0], label[b.1 * w + b.0]);
+            if !joined && !maze.rng.random_bool(self.extra_doors.clamp(0.0, 1.0)) {
+                continue;
+            }
+            let between = wall(a, b);
+            let door = in_room[a.1 * w + a.0] || in_room[b.1 * w + b.0];
+            maze.grid[between.y][between.x] = if door { Cell::Door } else { Cell::Passage };
+        }
+
+        if self.remove_dead_ends {
+            let dead_end =
+                |maze: &Maze, p: Pos| p != maze.start && maze.open_neighbours(p).len() <= 1;
+            let mut ends: Vec<Pos> = (0..maze.y)
+                .flat_map(|y| (0..maze.x).map(move |x| Pos::new(x, y)))
+                .filter(|&p| maze.grid[p.y][p.x].is_passable() && dead_end(maze, p))
+                .collect();
+            while let Some(p) = ends.pop() {
+                if maze.grid[p.y][p.x].is_wall() || !dead_end(maze, p) {
+                    continue;
+                }
+                maze.grid[p.y][p.x] = Cell::Wall;
+                ends.extend(maze.open_neighbours(p));
+            }
+        }
+    }
+}
+
+/// Cellular automaton caves. Starts from random noise and smooths it with birth and survival
+/// rules, then tunnels between the caverns that came out separate. Works on grid cells like
+/// Prim's, so the result is open caverns rather than one-cell corridors.
+pub struct Cave {
+    pub fill: f64,       // chance for each cell to start out as rock
+    pub birth: usize,    // an open cell turns to rock with at least this many rock neighbours
+    pub survival: usize, // rock stays rock with at least this many rock neighbours
+    pub iterations: usize,
+}
+
+impl Cave {
+    pub fn new(fill: f64, birth: usize, survival: usize, iterations: usize) -> Self {
+        Self {
+            fill,
+            birth,
+            survival,
+            iterations,
+        }
+    }
+
+    // One round of the birth and survival rules over the whole cave
+    fn smooth(&self, maze: &mut Maze, interior: &[Pos]) {
+        let (x, y) = (maze.x, maze.y);
+        let mut next = maze.grid.clone();
+        for &p in interior {
+            // Anything off the grid or outside the mask counts as rock
+            let rock = (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                .filter(|&d| d != (0, 0))
+                .filter(|&(dx, dy)| {
+                    p.offset(dx, dy)
+                        .is_none_or(|q| q.x >= x || q.y >= y || maze.grid[q.y][q.x].is_wall())
+                })
+                .count();
+            let was_rock = maze.grid[p.y][p.x].is_wall();
+            let is_rock = if was_rock {
+                rock >= self.survival
+            } else {
+                rock >= self.birth
+            };
+            next[p.y][p.x] = if is_rock { Cell::Wall } else { Cell::Passage };
+        }
+        maze.grid = next;
+    }
+}
+
+impl Default for Cave {
+    fn default() -> Self {
+        Self::new(0.45, 5, 4, 4)
+    }
+}
+
+impl Generator for Cave {
+    fn name(&self) -> &'static str {
+        "cave"
+    }
+
+    fn supports_wrap(&self) -> bool {
+        false
+    }
+
+    fn carve(&mut self, maze: &mut Maze) {
+        let (x, y) = (maze.x, maze.y);
+        let carvable = |maze: &Maze, p: Pos| {
+            p.x > 0 && p.y > 0 && p.x + 1 < x && p.y + 1 < y && maze.in_mask(p)
+        };
+        let interior: Vec<Pos> = (0..y)
+            .flat_map(|gy| (0..x).map(move |gx| Pos::new(gx, gy)))
+            .filter(|&p| carvable(maze, p))
+            .collect();
+
+        // Smoothing can wear a small cave away to nothing, so the noise thins out until at
+        // least a quarter of it stays open. If even no rock at all is too much for the rules,
+        // the cave is left as one open chamber.
+        let least = interior.len().div_ceil(4);
+        let mut fill = self.fill.clamp(0.0, 1.0);
+        loop {
+            noise(maze, &interior, fill);
+            for _ in 0..self.iterations {
+                self.smooth(maze, &interior);
+            }
+            let open = interior
+                .iter()
+                .filter(|p| maze.grid[p.y][p.x].is_passable())
+                .count();
+            if open >= least {
+                break;
+            }
+            if fill == 0.0 {
+                noise(maze, &interior, 0.0);
+                break;
+            }
+            fill = (fill - 0.1).max(0.0);
+        }
+        let start = maze.start;
+        maze.grid[start.y][start.x] = Cell::Passage;
+
+        // Grow outwards from everything joined to the start until another cavern turns up,
+        // then dig the shortest tunnel to it. Repeats until nothing open is left unreached.
+        let index = |p: Pos| p.y * x + p.x;
+        let mut joined = vec![false; x * y];
+        let mut frontier = vec![start];
+        joined[index(start)] = true;
+        loop {
+            while let Some(p) = frontier.pop() {
+                for q in maze.open_neighbours(p) {
+                    if !joined[index(q)] {
+                        joined[index(q)] = true;
+                        frontier.push(q);
+                    }
+                }
+            }
+
+            let mut prev: Vec<Option<Pos>> = vec![None; x * y];
+            let mut seen = joined.clone();
+            let mut queue: VecDeque<Pos> = interior
+                .iter()
+                .copied()
+                .filter(|&p| joined[index(p)])
+                .collect();
+            let mut found = None;
+            'search: while let Some(p) = queue.pop_front() {
+                for &(dx, dy) in DIRECTIONS.iter() {
+                    let Some(q) = p.offset(dx, dy).filter(|&q| carvable(maze, q)) else {
+                        continue;
+                    };
+                    if seen[index(q)] {
+                        continue;
+                    }
+                    seen[index(q)] = true;
+                    prev[index(q)] = Some(p);
+                    if maze.grid[q.y][q.x].is_passable() {
+                        found = Some(q);
+                        break 'search;
+                    }
+                    queue.push_back(q);
+                }
+            }
+            let Some(mut p) = found else {
+                break;
+            };
+
+            // Dig back to the joined area and carry on from the cavern just reached
+            frontier.push(p);
+            joined[index(p)] = true;
+            while let Some(q) = prev[index(p)].filter(|&q| !joined[index(q)]) {
+                maze.grid[q.y][q.x] = Cell::Passage;
+                joined[index(q)] = true;
+                frontier.push(q);
+                p = q;
+            }
+        }
+
+        // Caverns cut off by the mask can't be reached, so they go back to rock
+        for &p in &interior {
+            if !joined[index(p)] {
+                maze.grid[p.y][p.x] = Cell::Wall;
+            }
+        }
+    }
+}
+
+// Each cell turns to rock with the chance `fill`
+fn noise(maze: &mut Maze, cells: &[Pos], fill: f64) {
+    for &p in cells {
+        maze.grid[p.y][p.x] = if maze.rng.random_bool(fill) {
+            Cell::Wall
+        } else {
+            Cell::Passage
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::maze::{ExitPlacement, Mask, MazeTrait, Pos};
+
+    // Open cells make a tree: every one of them is reachable from the start and there is
+    // one fewer opening between neighbours than there are open cells
+    fn is_perfect(maze: &Maze) -> bool {
+        let open = |p: Pos| p.x < maze.x && p.y < maze.y && maze.grid[p.y][p.x].is_passable();
+        let cells: Vec<Pos> = (0..maze.y)
+            .flat_map(|y| (0..maze.x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| open(p))
+            .collect();
+        let joins: usize = cells
+            .iter()
+            .map(|&p| {
+                usize::from(open(Pos::new(p.x + 1, p.y)))
+                    + usize::from(open(Pos::new(p.x, p.y + 1)))
+            })
+            .sum();
+
+        let mut seen = HashSet::from([maze.start]);
+        let mut stack = vec![maze.start];
+        while let Some(p) = stack.pop() {
+            for (dx, dy) in DIRECTIONS {
+                if let Some(n) = p.offset(dx, dy)
+                    && open(n)
+                    && seen.insert(n)
+                {
+                    stack.push(n);
+                }
+            }
+        }
+        seen.len() == cells.len() && joins + 1 == cells.len()
+    }
+
+    // Every open cell can be reached from the start
+    fn joined_up(maze: &Maze) -> bool {
+        let open = maze
+            .grid
+            .iter()
+            .flatten()
+            .filter(|c| c.is_passable())
+            .count();
+        let mut seen = HashSet::from([maze.start]);
+        let mut stack = vec![maze.start];
+        while let Some(p) = stack.pop() {
+            for n in maze.open_neighbours(p) {
+                if seen.insert(n) {
+                    stack.push(n);
+                }
+            }
+        }
+        seen.len() == open
+    }
+
+    // Everything but the generators that leave loops on purpose
+    fn perfect() -> impl Iterator<Item = Box<dyn Generator>> {
+        all()
+            .into_iter()
+            .filter(|g| !matches!(g.name(), "dungeon" | "cave"))
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        for mut generator in all() {
+            let mut a = Maze::with_seed(31, 31, 8);
+            let mut b = Maze::with_seed(31, 31, 8);
+            a.generate_with(generator.as_mut()).unwrap();
+            b.generate_with(generator.as_mut()).unwrap();
+            assert_eq!(a.to_string(), b.to_string(), "{}", generator.name());
+            assert_eq!(a.seed(), Some(8));
+        }
+    }
+
+    #[test]
+    fn perfect_mazes() {
+        for mut generator in perfect() {
+            for size in [5, 21, 31] {
+                for seed in 0..3 {
+                    let mut maze = Maze::with_seed(size, size, seed);
+                    maze.generate_with(generator.as_mut()).unwrap();
+                    assert!(
+                        is_perfect(&maze),
+                        "{} {}x{} seed {}\n{}",
+                        generator.name(),
+                        size,
+                        size,
+                        seed,
+                        maze
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn masked_mazes() {
+        let mask = Mask::from_ascii("XXXXXXX\nXX...XX\nXX...XX\nXXXXXXX\n").unwrap();
+        for mut generator in perfect().filter(|g| g.supports_mask()) {
+            for seed in 0..3 {
+                let mut maze = Maze::with_mask(mask.clone(), seed).unwrap();
+                maze.generate_with(generator.as_mut()).unwrap();
+                assert!(
+                    is_perfect(&maze),
+                    "{} seed {}\n{}",
+                    generator.name(),
+                    seed,
+                    maze
+                );
+                assert!(
+                    !maze.grid[3][5].is_passable(),
+                    "{} seed {}",
+                    generator.name(),
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_shapes_are_refused() {
+        let mut maze = Maze::with_mask(Mask::new(4, 3), 1).unwrap();
+        assert_eq!(
+            maze.generate_with(&mut Eller),
+            Err(GenerateError::Mask("eller"))
+        );
+        let mut maze = Maze::with_seed(21, 21, 1);
+        maze.set_wrap(true);
+        assert_eq!(
+            maze.generate_with(&mut Eller),
+            Err(GenerateError::Wrap("eller"))
+        );
+        assert_eq!(
+            maze.generate_with(&mut Dungeon::default()),
+            Err(GenerateError::Wrap("dungeon"))
+        );
+    }
+
+    #[test]
+    fn dungeons_join_up_without_dead_ends() {
+        for seed in 0..5 {
+            let mut maze = Maze::with_seed(41, 31, seed);
+            maze.generate_with(&mut Dungeon::default()).unwrap();
+            assert!(joined_up(&maze), "seed {}\n{}", seed, maze);
+            let open: Vec<Pos> = (0..maze.y)
+                .flat_map(|y| (0..maze.x).map(move |x| Pos::new(x, y)))
+                .filter(|p| maze.grid[p.y][p.x].is_passable())
+                .collect();
+            assert!(
+                open.iter()
+                    .all(|&p| p == maze.start || maze.open_neighbours(p).len() > 1),
+                "seed {}\n{}",
+                seed,
+                maze
+            );
+            assert!(open.iter().any(|p| maze.grid[p.y][p.x] == Cell::Door));
+        }
+    }
+
+    #[test]
+    fn small_caves_keep_some_room() {
+        let mask = Mask::from_ascii("XXXXXXXX\nXXXXXXXX\nXXXXXXXX\nXXXXXXXX\nXXXXXXXX\nXXXXXXXX\n")
+            .unwrap();
+        for seed in 0..30 {
+            let mut maze = Maze::with_mask(mask.clone(), seed).unwrap();
+            maze.set_exit_placement(ExitPlacement::Farthest);
+            maze.generate_with(&mut Cave::default()).unwrap();
+            let open = maze
+                .grid
+                .iter()
+                .flatten()
+                .filter(|c| c.is_passable())
+                .count();
+            assert!(
+                open >= 15 * 11 / 4,
+                "seed {} left {} open cells",
+                seed,
+                open
+            );
+            assert!(maze.exit().is_some(), "seed {}", seed);
+            assert!(joined_up(&maze), "seed {}\n{}", seed, maze);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+use super::{Cell, OutOfBounds, Pos};
+
+// Cell by cell access to anything that stores a maze grid. Only the size and the checked get
+// and set differ between storages, the rest is built on top of them.
+pub trait Grid {
+    fn size(&self) -> (usize, usize);
+
+    fn try_get(&self, pos: Pos) -> Result<Cell, OutOfBounds>;
+
+    // Returns the cell that was replaced
+    fn try_set(&mut self, pos: Pos, c: Cell) -> Result<Cell, OutOfBounds>;
+
+    fn contains(&self, pos: Pos) -> bool {
+        let (x, y) = self.size();
+        pos.x < x && pos.y < y
+    }
+
+    fn check(&self, pos: Pos) -> Result<(), OutOfBounds> {
+        if self.contains(pos) {
+            Ok(())
+        } else {
+            Err(OutOfBounds {
+                pos,
+                size: self.size(),
+            })
+        }
+    }
+
+    fn try_swap(&mut self, a: Pos, b: Pos) -> Result<(), OutOfBounds> {
+        let (ca, cb) = (self.try_get(a)?, self.try_get(b)?);
+        self.try_set(a, cb)?;
+        self.try_set(b, ca)?;
+        Ok(())
+    }
+
+    fn get_at(&self, pos: Pos) -> Cell {
+        match self.try_get(pos) {
+            Ok(c) => c,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn insert_at(&mut self, pos: Pos, c: Cell) {
+        if let Err(e) = self.try_set(pos, c) {
+            panic!("{}", e);
+        }
+    }
+
+    fn swap_at(&mut self, a: Pos, b: Pos) {
+        if let Err(e) = self.try_swap(a, b) {
+            panic!("{}", e);
+        }
+    }
+
+    // The grid cell one step from `pos`, None off the edge
+    fn neighbour(&self, pos: Pos, dx: isize, dy: isize) -> Option<Pos> {
+        pos.offset(dx, dy).filter(|&n| self.contains(n))
+    }
+
+    // Passable cells next to pos, in the same order as the generators' directions
+    fn open_neighbours(&self, pos: Pos) -> Vec<Pos> {
+        [(0, 1), (1, 0), (0, -1), (-1, 0)]
+            .iter()
+            .filter_map(|&(dx, dy)| self.neighbour(pos, dx, dy))
+            .filter(|&n| self.try_get(n).is_ok_and(Cell::is_passable))
+            .collect()
+    }
+}
+
+// Two characters a cell with `\r\n` line ends, shared by the `Display` impls
+pub(super) fn write_grid<G: Grid>(grid: &G, f: &mut fmt::Formatter) -> fmt::Result {
+    let (x, y) = grid.size();
+    for gy in 0..y {
+        for gx in 0..x {
+            write!(f, "{}", grid.get_at(Pos::new(gx, gy)))?;
+        }
+        write!(f, "\r\n")?;
+    }
+    Ok(())
+}
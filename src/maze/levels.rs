@@ -0,0 +1,395 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::{Rng as _, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{Cell, Grid, Maze, MazeTrait, Pos};
+
+pub(super) const NAME: &str = "backtracker-3d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Pos3 {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize, // floor, 0 at the bottom
+}
+
+impl Pos3 {
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn flat(self) -> Pos {
+        Pos::new(self.x, self.y)
+    }
+}
+
+impl std::fmt::Display for Pos3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({}, {}, floor {})", self.x, self.y, self.z)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    SideBySide,
+    Floor(usize),
+}
+
+// Stacked floors of the same size. Stairs always join a cell to the one straight above it,
+// and which cells they join is kept apart from the grid so a start or exit drawn on top of
+// a staircase doesn't cut the way up.
+pub struct Maze3d {
+    floors: Vec<Maze>,
+    stairs: HashSet<Pos3>, // lower end of every staircase
+    start: Pos3,
+    exit: Option<Pos3>,
+    seed: u64,
+    stair_chance: f64,
+    rng: ChaCha8Rng,
+}
+
+impl Maze3d {
+    pub fn new(x: usize, y: usize, floors: usize, seed: u64) -> Self {
+        if floors == 0 {
+            panic!("A {}x{} maze needs at least one floor", x, y);
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let start = Pos3::new(
+            rng.random_range(0..(x - 1) / 2) * 2 + 1,
+            rng.random_range(0..(y - 1) / 2) * 2 + 1,
+            0,
+        );
+        // Floors are only carved through `rng` below, so they have no seed of their own
+        Self {
+            floors: (0..floors)
+                .map(|_| Maze::with_rng(x, y, ChaCha8Rng::seed_from_u64(seed)))
+                .collect(),
+            stairs: HashSet::new(),
+            start,
+            exit: None,
+            seed,
+            stair_chance: 0.2,
+            rng,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn floors(&self) -> &[Maze] {
+        &self.floors
+    }
+
+    pub fn floor(&self, z: usize) -> &Maze {
+        &self.floors[z]
+    }
+
+    pub fn start(&self) -> Pos3 {
+        self.start
+    }
+
+    pub fn exit(&self) -> Option<Pos3> {
+        self.exit
+    }
+
+    // How often the carving takes the stairs when it could also stay on the floor
+    pub fn set_stair_chance(&mut self, chance: f64) {
+        self.stair_chance = chance.clamp(0.0, 1.0);
+    }
+
+    pub fn get_at(&self, pos: Pos3) -> Cell {
+        self.floors[pos.z].get_at(pos.flat())
+    }
+
+    pub fn has_stairs_up(&self, pos: Pos3) -> bool {
+        self.stairs.contains(&pos)
+    }
+
+    pub fn has_stairs_down(&self, pos: Pos3) -> bool {
+        pos.z > 0 && self.stairs.contains(&Pos3::new(pos.x, pos.y, pos.z - 1))
+    }
+
+    // Every cell reachable in one step, including up and down the stairs
+    pub fn moves(&self, pos: Pos3) -> Vec<Pos3> {
+        let mut found: Vec<Pos3> = self.floors[pos.z]
+            .open_neighbours(pos.flat())
+            .into_iter()
+            .map(|p| Pos3::new(p.x, p.y, pos.z))
+            .collect();
+        if self.has_stairs_up(pos) {
+            found.push(Pos3::new(pos.x, pos.y, pos.z + 1));
+        }
+        if self.has_stairs_down(pos) {
+            found.push(Pos3::new(pos.x, pos.y, pos.z - 1));
+        }
+        found
+    }
+
+    pub fn can_move(&self, from: Pos3, to: Pos3) -> bool {
+        self.moves(from).contains(&to)
+    }
+
+    // A 3D recursive backtracker over the cell lattice of every floor, then the exit goes on
+    // the cell furthest from the start
+    pub fn generate(&mut self) {
+        let (x, y) = self.floors[0].size();
+        let (w, h, d) = ((x - 1) / 2, (y - 1) / 2, self.floors.len());
+        for floor in &mut self.floors {
+            floor.grid = vec![vec![Cell::Wall; x]; y];
+            floor.exit = None;
+            floor.algorithm = NAME.into();
+        }
+        self.stairs.clear();
+
+        let to_grid = |c: (usize, usize, usize)| Pos3::new(c.0 * 2 + 1, c.1 * 2 + 1, c.2);
+        let mut visited = vec![false; w * h * d];
+        let index = |c: (usize, usize, usize)| (c.2 * h + c.1) * w + c.0;
+        let first = ((self.start.x - 1) / 2, (self.start.y - 1) / 2, self.start.z);
+        visited[index(first)] = true;
+        self.open(to_grid(first));
+        let mut stack = vec![first];
+
+        while let Some(&(cx, cy, cz)) = stack.last() {
+            let mut flat = vec![];
+            let mut vertical = vec![];
+            for (dx, dy, dz) in [
+                (0, 1, 0),
+                (1, 0, 0),
+                (0, -1, 0),
+                (-1, 0, 0),
+                (0, 0, 1),
+                (0, 0, -1),
+            ] {
+                let (Some(nx), Some(ny), Some(nz)) = (
+                    cx.checked_add_signed(dx),
+                    cy.checked_add_signed(dy),
+                    cz.checked_add_signed(dz),
+                ) else {
+                    continue;
+                };
+                if nx < w && ny < h && nz < d && !visited[index((nx, ny, nz))] {
+                    if dz == 0 { &mut flat } else { &mut vertical }.push((nx, ny, nz));
+                }
+            }
+            let options = match (flat.is_empty(), vertical.is_empty()) {
+                (true, true) => {
+                    stack.pop();
+                    continue;
+                }
+                (false, false) if self.rng.random_bool(self.stair_chance) => vertical,
+                (false, _) => flat,
+                (true, false) => vertical,
+            };
+            let next = options[self.rng.random_range(0..options.len())];
+            visited[index(next)] = true;
+
+            let (a, b) = (to_grid((cx, cy, cz)), to_grid(next));
+            if a.z == b.z {
+                self.open(b);
+                self.open(Pos3::new((a.x + b.x) / 2, (a.y + b.y) / 2, a.z));
+            } else {
+                self.open(b);
+                self.stairs.insert(if a.z < b.z { a } else { b });
+            }
+            stack.push(next);
+        }
+
+        for lower in self.stairs.clone() {
+            self.mark_stairs(lower);
+            self.mark_stairs(Pos3::new(lower.x, lower.y, lower.z + 1));
+        }
+
+        // Every floor starts where the way in from the start first reaches it. Above and below
+        // the start's own floor that is a staircase, which keeps its symbol so it still shows
+        // where the floor is entered.
+        let dist = self.distances(self.start);
+        for (z, floor) in self.floors.iter_mut().enumerate() {
+            if let Some(&(entry, _)) = dist.iter().find(|(p, _)| p.z == z) {
+                floor.start = entry.flat();
+            }
+        }
+        let start = self.start;
+        self.floors[start.z].grid[start.y][start.x] = Cell::Start;
+
+        self.exit = dist
+            .iter()
+            .max_by_key(|(_, d)| *d)
+            .map(|&(p, _)| p)
+            .filter(|&p| p != self.start);
+        if let Some(exit) = self.exit {
+            self.floors[exit.z].exit = Some(exit.flat());
+            self.floors[exit.z].grid[exit.y][exit.x] = Cell::Exit;
+        }
+    }
+
+    fn open(&mut self, pos: Pos3) {
+        self.floors[pos.z].grid[pos.y][pos.x] = Cell::Passage;
+    }
+
+    fn mark_stairs(&mut self, pos: Pos3) {
+        self.floors[pos.z].grid[pos.y][pos.x] =
+            match (self.has_stairs_up(pos), self.has_stairs_down(pos)) {
+                (true, true) => Cell::StairsBoth,
+                (true, false) => Cell::StairsUp,
+                (false, true) => Cell::StairsDown,
+                (false, false) => Cell::Passage,
+            };
+    }
+
+    // Step counts to every reachable cell, in the order they were reached
+    pub fn distances(&self, from: Pos3) -> Vec<(Pos3, usize)> {
+        let mut seen = HashSet::from([from]);
+        let mut found = vec![(from, 0)];
+        let mut queue = VecDeque::from([(from, 0)]);
+        while let Some((pos, d)) = queue.pop_front() {
+            for n in self.moves(pos) {
+                if seen.insert(n) {
+                    found.push((n, d + 1));
+                    queue.push_back((n, d + 1));
+                }
+            }
+        }
+        found
+    }
+
+    // Shortest route between two cells, taking the stairs where needed
+    pub fn path(&self, from: Pos3, to: Pos3) -> Option<Vec<Pos3>> {
+        let mut prev = std::collections::HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                let mut path = vec![to];
+                while path[path.len() - 1] != from {
+                    path.push(prev[&path[path.len() - 1]]);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for n in self.moves(pos) {
+                if let std::collections::hash_map::Entry::Vacant(e) = prev.entry(n) {
+                    e.insert(pos);
+                    queue.push_back(n);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn solve(&self) -> Option<Vec<Pos3>> {
+        self.path(self.start, self.exit?)
+    }
+
+    // Marks plain passages along the path, stairs, start and exit keep their look
+    pub fn mark_path(&mut self, path: &[Pos3]) {
+        for &pos in path {
+            let cell = &mut self.floors[pos.z].grid[pos.y][pos.x];
+            if *cell == Cell::Passage {
+                *cell = Cell::Path;
+            }
+        }
+    }
+
+    pub fn render(&self, layout: Layout) -> String {
+        match layout {
+            Layout::Floor(z) => self.floors[z].to_string(),
+            Layout::SideBySide => {
+                let mut out = String::new();
+                let rows = self.floors[0].size().1;
+                for y in 0..rows {
+                    let line: Vec<String> = self
+                        .floors
+                        .iter()
+                        .map(|f| f.grid[y].iter().map(|c| c.symbol()).collect())
+                        .collect();
+                    out.push_str(&line.join("  "));
+                    out.push_str("\r\n");
+                }
+                out
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Maze3d {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(Layout::SideBySide))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stacked(floors: usize, seed: u64) -> Maze3d {
+        let mut maze = Maze3d::new(11, 9, floors, seed);
+        maze.set_stair_chance(0.5);
+        maze.generate();
+        maze
+    }
+
+    #[test]
+    fn a_single_cell_stack_is_all_stairs() {
+        let mut maze = Maze3d::new(3, 3, 3, 1);
+        maze.generate();
+        let (bottom, middle, top) = (Pos3::new(1, 1, 0), Pos3::new(1, 1, 1), Pos3::new(1, 1, 2));
+        assert_eq!(maze.start(), bottom);
+        assert_eq!(maze.exit(), Some(top));
+        assert_eq!(maze.moves(bottom), [middle]);
+        assert_eq!(maze.moves(middle), [top, bottom]);
+        assert!(maze.can_move(top, middle));
+        assert!(!maze.can_move(bottom, top));
+        assert_eq!(maze.solve(), Some(vec![bottom, middle, top]));
+
+        assert_eq!(maze.get_at(bottom), Cell::Start);
+        assert_eq!(maze.get_at(middle), Cell::StairsBoth);
+        assert_eq!(maze.get_at(top), Cell::Exit);
+        assert_eq!(maze.floor(1).start(), middle.flat());
+    }
+
+    #[test]
+    fn stairs_join_both_ways() {
+        for seed in 0..5 {
+            let maze = stacked(3, seed);
+            assert!(!maze.stairs.is_empty(), "seed {}", seed);
+            for &lower in &maze.stairs {
+                let upper = Pos3::new(lower.x, lower.y, lower.z + 1);
+                assert!(maze.can_move(lower, upper), "{} seed {}", lower, seed);
+                assert!(maze.can_move(upper, lower), "{} seed {}", upper, seed);
+                for end in [lower, upper] {
+                    if end != maze.start() && Some(end) != maze.exit() {
+                        assert!(maze.get_at(end).is_stairs(), "{} seed {}", end, seed);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn paths_take_the_stairs() {
+        for seed in 0..5 {
+            let maze = stacked(3, seed);
+            let all = maze.distances(maze.start());
+            let (far, _) = *all.iter().find(|(p, _)| p.z == 2).unwrap();
+            let path = maze.path(maze.start(), far).unwrap();
+            assert_eq!(path.first(), Some(&maze.start()));
+            assert_eq!(path.last(), Some(&far));
+            assert!(path.windows(2).all(|w| maze.can_move(w[0], w[1])));
+            assert!(path.windows(2).any(|w| w[0].z != w[1].z));
+
+            // Every floor is entered somewhere the start can be reached from
+            for z in 0..3 {
+                let entry = maze.floor(z).start();
+                let entry = Pos3::new(entry.x, entry.y, z);
+                assert!(maze.path(entry, maze.start()).is_some(), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one floor")]
+    fn no_floors_is_refused() {
+        Maze3d::new(11, 9, 0, 1);
+    }
+}
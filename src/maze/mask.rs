@@ -0,0 +1,372 @@
+use std::{fmt, fs, io, path::Path};
+
+use rand::Rng as _;
+
+use super::{Maze, MazeTrait, OutOfBounds, Pos};
+
+// Which cells of the lattice a maze may use, one entry per maze cell rather than per grid
+// cell, so a WxH mask becomes a (2W + 1)x(2H + 1) grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+#[derive(Debug)]
+pub enum MaskError {
+    Io(io::Error),
+    Header(String),
+    Truncated { expected: usize, found: usize },
+    Empty,
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaskError::Io(e) => write!(f, "could not read mask: {}", e),
+            MaskError::Header(msg) => write!(f, "bad PBM header: {}", msg),
+            MaskError::Truncated { expected, found } => {
+                write!(f, "mask needs {} pixels but only has {}", expected, found)
+            }
+            MaskError::Empty => write!(f, "mask has no cells to carve"),
+        }
+    }
+}
+
+impl std::error::Error for MaskError {}
+
+impl From<io::Error> for MaskError {
+    fn from(e: io::Error) -> Self {
+        MaskError::Io(e)
+    }
+}
+
+impl Mask {
+    // Everything allowed
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![true; width * height],
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, allowed: bool) -> Result<(), OutOfBounds> {
+        if x >= self.width || y >= self.height {
+            return Err(OutOfBounds {
+                pos: Pos::new(x, y),
+                size: (self.width, self.height),
+            });
+        }
+        self.cells[y * self.width + x] = allowed;
+        Ok(())
+    }
+
+    pub fn count(&self) -> usize {
+        self.cells.iter().filter(|&&c| c).count()
+    }
+
+    // Spaces and dots are outside the shape, any other character is inside
+    pub fn from_ascii(text: &str) -> Result<Self, MaskError> {
+        let mut lines: Vec<&str> = text.lines().map(|l| l.trim_end_matches('\r')).collect();
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut cells = vec![false; width * lines.len()];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                cells[y * width + x] = c != ' ' && c != '.';
+            }
+        }
+        Self {
+            width,
+            height: lines.len(),
+            cells,
+        }
+        .non_empty()
+    }
+
+    // Plain (P1) or raw (P4) bitmaps, black pixels are inside the shape
+    pub fn from_pbm(bytes: &[u8]) -> Result<Self, MaskError> {
+        let mut at = 0;
+        let magic = next_token(bytes, &mut at).ok_or(MaskError::Header("missing magic".into()))?;
+        let width = next_number(bytes, &mut at)?;
+        let height = next_number(bytes, &mut at)?;
+        // The header is checked against the data before anything is allocated for it, so a
+        // huge size can't run away with memory
+        let len = width
+            .checked_mul(height)
+            .ok_or_else(|| MaskError::Header(format!("{}x{} is too large", width, height)))?;
+
+        let cells = match magic {
+            b"P1" => {
+                let bits = bytes[at..].iter().filter(|b| matches!(b, b'0' | b'1'));
+                let found = bits.clone().count();
+                if found < len {
+                    return Err(MaskError::Truncated {
+                        expected: len,
+                        found,
+                    });
+                }
+                bits.take(len).map(|&b| b == b'1').collect()
+            }
+            b"P4" => {
+                // Exactly one whitespace byte separates the header from the pixel rows
+                let data = &bytes[(at + 1).min(bytes.len())..];
+                let row_bytes = width.div_ceil(8);
+                if row_bytes.checked_mul(height).is_none_or(|n| data.len() < n) {
+                    return Err(MaskError::Truncated {
+                        expected: len,
+                        found: data.len().saturating_mul(8),
+                    });
+                }
+                (0..height)
+                    .flat_map(|y| {
+                        let row = &data[y * row_bytes..];
+                        (0..width).map(move |x| row[x / 8] & (0x80 >> (x % 8)) != 0)
+                    })
+                    .collect()
+            }
+            other => {
+                return Err(MaskError::Header(format!(
+                    "unsupported magic {:?}",
+                    String::from_utf8_lossy(other)
+                )));
+            }
+        };
+        Self {
+            width,
+            height,
+            cells,
+        }
+        .non_empty()
+    }
+
+    // Picks the format from the file contents, PBM when it starts with a P1/P4 magic
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MaskError> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(b"P1") || bytes.starts_with(b"P4") {
+            Self::from_pbm(&bytes)
+        } else {
+            Self::from_ascii(&String::from_utf8_lossy(&bytes))
+        }
+    }
+
+    fn non_empty(self) -> Result<Self, MaskError> {
+        if self.count() == 0 {
+            Err(MaskError::Empty)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// PBM header tokens are separated by whitespace, and `#` starts a comment up to the line end
+fn next_token<'a>(bytes: &'a [u8], at: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        while *at < bytes.len() && bytes[*at].is_ascii_whitespace() {
+            *at += 1;
+        }
+        if *at < bytes.len() && bytes[*at] == b'#' {
+            while *at < bytes.len() && bytes[*at] != b'\n' {
+                *at += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let begin = *at;
+    while *at < bytes.len() && !bytes[*at].is_ascii_whitespace() {
+        *at += 1;
+    }
+    (*at > begin).then(|| &bytes[begin..*at])
+}
+
+fn next_number(bytes: &[u8], at: &mut usize) -> Result<usize, MaskError> {
+    let token = next_token(bytes, at).ok_or(MaskError::Header("missing size".into()))?;
+    std::str::from_utf8(token)
+        .ok()
+        .and_then(|t| t.parse().ok())
+        .ok_or_else(|| MaskError::Header(format!("bad size {:?}", String::from_utf8_lossy(token))))
+}
+
+impl Maze {
+    // A maze that only carves inside the mask, starting from a random allowed cell
+    pub fn with_mask(mask: Mask, seed: u64) -> Result<Self, MaskError> {
+        let (w, h) = mask.size();
+        let mut maze = Self::with_seed(w * 2 + 1, h * 2 + 1, seed);
+        let allowed: Vec<Pos> = (0..h)
+            .flat_map(|y| (0..w).map(move |x| Pos::new(x, y)))
+            .filter(|p| mask.get(p.x, p.y))
+            .collect();
+        if allowed.is_empty() {
+            return Err(MaskError::Empty);
+        }
+        let cell = allowed[maze.rng.random_range(0..allowed.len())];
+        maze.start = Pos::new(cell.x * 2 + 1, cell.y * 2 + 1);
+        maze.mask = Some(mask);
+        Ok(maze)
+    }
+
+    pub fn mask(&self) -> Option<&Mask> {
+        self.mask.as_ref()
+    }
+
+    // Whether the mask lets a grid cell be carved. Lattice cells follow their mask entry,
+    // walls between two cells need both and corner posts need all four around them.
+    pub(super) fn in_mask(&self, pos: Pos) -> bool {
+        let Some(mask) = &self.mask else {
+            return true;
+        };
+        let xs: &[usize] = if pos.x % 2 == 1 {
+            &[pos.x / 2]
+        } else if pos.x == 0 {
+            return false;
+        } else {
+            &[pos.x / 2 - 1, pos.x / 2]
+        };
+        let ys: &[usize] = if pos.y % 2 == 1 {
+            &[pos.y / 2]
+        } else if pos.y == 0 {
+            return false;
+        } else {
+            &[pos.y / 2 - 1, pos.y / 2]
+        };
+        xs.iter().all(|&x| ys.iter().all(|&y| mask.get(x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Grid;
+
+    // The same L shape in each format
+    const SHAPE: [[bool; 3]; 2] = [[true, false, false], [true, true, true]];
+
+    fn assert_shape(mask: &Mask) {
+        assert_eq!(mask.size(), (3, 2));
+        for (y, row) in SHAPE.iter().enumerate() {
+            for (x, &allowed) in row.iter().enumerate() {
+                assert_eq!(mask.get(x, y), allowed, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_ascii() {
+        assert_shape(&Mask::from_ascii("X..\nXXX\n\n").unwrap());
+        assert_shape(&Mask::from_ascii("#\n###").unwrap());
+    }
+
+    #[test]
+    fn reads_plain_pbm() {
+        assert_shape(&Mask::from_pbm(b"P1\n# an L\n3 2\n1 0 0\n111\n").unwrap());
+    }
+
+    #[test]
+    fn reads_raw_pbm() {
+        assert_shape(&Mask::from_pbm(b"P4\n3 2\n\x80\xe0").unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_pbm() {
+        assert!(matches!(
+            Mask::from_pbm(b"P1 3 2\n1 0 0 1"),
+            Err(MaskError::Truncated {
+                expected: 6,
+                found: 4
+            })
+        ));
+        assert!(matches!(
+            Mask::from_pbm(b"P4 3 2\n\x80"),
+            Err(MaskError::Truncated { expected: 6, .. })
+        ));
+        assert!(matches!(
+            Mask::from_pbm(b"P1 18446744073709551615 2\n"),
+            Err(MaskError::Header(_))
+        ));
+        assert!(matches!(
+            Mask::from_pbm(b"P4 18446744073709551615 1\n\xff"),
+            Err(MaskError::Truncated { .. })
+        ));
+        assert!(matches!(
+            Mask::from_pbm(b"P2 3 2\n"),
+            Err(MaskError::Header(_))
+        ));
+        assert!(matches!(
+            Mask::from_pbm(b"P1 2 1\n0 0"),
+            Err(MaskError::Empty)
+        ));
+    }
+
+    #[test]
+    fn loads_either_format() {
+        let dir = std::env::temp_dir();
+        let ascii = dir.join(format!("mask-{}.txt", std::process::id()));
+        let pbm = dir.join(format!("mask-{}.pbm", std::process::id()));
+        fs::write(&ascii, "X..\nXXX\n").unwrap();
+        fs::write(&pbm, b"P4\n3 2\n\x80\xe0").unwrap();
+        let loaded = (Mask::load(&ascii), Mask::load(&pbm));
+        fs::remove_file(&ascii).unwrap();
+        fs::remove_file(&pbm).unwrap();
+
+        assert_shape(&loaded.0.unwrap());
+        assert_shape(&loaded.1.unwrap());
+        assert!(matches!(
+            Mask::load(dir.join("no-such-mask")),
+            Err(MaskError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn set_stays_in_bounds() {
+        let mut mask = Mask::new(3, 2);
+        mask.set(2, 1, false).unwrap();
+        assert!(!mask.get(2, 1));
+        assert_eq!(mask.count(), 5);
+        assert_eq!(
+            mask.set(3, 0, false),
+            Err(OutOfBounds {
+                pos: Pos::new(3, 0),
+                size: (3, 2)
+            })
+        );
+    }
+
+    #[test]
+    fn masked_mazes_stay_inside() {
+        // An L has only one way through it
+        let mut maze = Maze::with_mask(Mask::from_ascii("X..\nXXX\n").unwrap(), 3).unwrap();
+        maze.generate();
+        assert_eq!(maze.size(), (7, 5));
+        let open: Vec<(usize, usize)> = (0..5)
+            .flat_map(|y| (0..7).map(move |x| (x, y)))
+            .filter(|&(x, y)| maze.get_at(Pos::new(x, y)).is_passable())
+            .collect();
+        assert_eq!(
+            open,
+            [(1, 1), (1, 2), (1, 3), (2, 3), (3, 3), (4, 3), (5, 3)]
+        );
+    }
+
+    #[test]
+    fn empty_masks_are_refused() {
+        let mut empty = Mask::new(2, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            empty.set(x, y, false).unwrap();
+        }
+        assert!(matches!(Maze::with_mask(empty, 1), Err(MaskError::Empty)));
+        assert!(matches!(Mask::from_ascii(". .\n"), Err(MaskError::Empty)));
+    }
+}
@@ -0,0 +1,245 @@
+use rand::{Rng as _, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{Cell, Grid, Maze, OutOfBounds, Pos, grid, rows::EllerRows};
+
+// One bit per grid cell in a flat vector, set for passages
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bits {
+    words: Vec<u64>,
+}
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize, on: bool) {
+        if on {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+}
+
+// A maze for grids too big for `Maze`, at one bit per grid cell instead of a byte and a heap
+// allocation per row. It only knows walls from passages, plus where the start and exit are,
+// so setting any other kind of cell stores it as a plain passage.
+pub struct PackedMaze {
+    x: usize,
+    y: usize,
+    bits: Bits,
+    start: Pos,
+    exit: Option<Pos>,
+    seed: u64,
+}
+
+impl PackedMaze {
+    pub fn with_seed(x: usize, y: usize, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let start_x = rng.random_range(0..(x - 1) / 2) * 2 + 1;
+        let start_y = rng.random_range(0..(y - 1) / 2) * 2 + 1;
+        Self {
+            x,
+            y,
+            bits: Bits::new(x * y),
+            start: Pos::new(start_x, start_y),
+            exit: None,
+            seed,
+        }
+    }
+
+    pub fn new(x: usize, y: usize) -> Self {
+        Self::with_seed(x, y, rand::rng().random())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Eller's, which only needs the current row on top of the grid itself, so the memory
+    // used stays at the bit grid plus a few words per column however tall the maze is
+    pub fn generate(&mut self) {
+        let (w, h) = ((self.x - 1) / 2, (self.y - 1) / 2);
+        self.bits = Bits::new(self.x * self.y);
+        for (cy, row) in EllerRows::new(w, h, self.seed).enumerate() {
+            let gy = cy * 2 + 1;
+            for cx in 0..w {
+                let gx = cx * 2 + 1;
+                self.open(gx, gy);
+                if row.east[cx] {
+                    self.open(gx + 1, gy);
+                }
+                if row.south[cx] {
+                    self.open(gx, gy + 1);
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, x: usize, y: usize) {
+        self.bits.set(y * self.x + x, true);
+    }
+
+    pub fn algorithm(&self) -> &'static str {
+        "eller"
+    }
+
+    pub fn start(&self) -> Pos {
+        self.start
+    }
+
+    pub fn exit(&self) -> Option<Pos> {
+        self.exit
+    }
+
+    pub fn set_exit(&mut self, exit: Option<Pos>) {
+        self.exit = exit;
+    }
+
+    // Unpacks into a regular maze, only sensible for grids that fit in memory as one
+    pub fn to_maze(&self) -> Maze {
+        let rows: Vec<Vec<u8>> = (0..self.y)
+            .map(|y| {
+                (0..self.x)
+                    .map(|x| self.get_at(Pos::new(x, y)).into())
+                    .collect()
+            })
+            .collect();
+        let mut maze = Maze::from_bytes(&rows);
+        maze.seed = Some(self.seed);
+        maze.algorithm = self.algorithm().into();
+        maze
+    }
+}
+
+impl Grid for PackedMaze {
+    fn size(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    fn try_get(&self, pos: Pos) -> Result<Cell, OutOfBounds> {
+        self.check(pos)?;
+        Ok(if !self.bits.get(pos.y * self.x + pos.x) {
+            Cell::Wall
+        } else if pos == self.start {
+            Cell::Start
+        } else if Some(pos) == self.exit {
+            Cell::Exit
+        } else {
+            Cell::Passage
+        })
+    }
+
+    // Start and exit move to wherever they are set, and the start stays put when something
+    // else is written over it since there is always one
+    fn try_set(&mut self, pos: Pos, c: Cell) -> Result<Cell, OutOfBounds> {
+        let before = self.try_get(pos)?;
+        match c {
+            Cell::Start => self.start = pos,
+            Cell::Exit => self.exit = Some(pos),
+            _ if Some(pos) == self.exit => self.exit = None,
+            _ => {}
+        }
+        self.bits.set(pos.y * self.x + pos.x, c.is_passable());
+        Ok(before)
+    }
+}
+
+impl From<&Maze> for PackedMaze {
+    fn from(maze: &Maze) -> Self {
+        let (x, y) = maze.size();
+        let mut packed = Self {
+            x,
+            y,
+            bits: Bits::new(x * y),
+            start: maze.start(),
+            exit: maze.exit(),
+            seed: maze.seed.unwrap_or_default(),
+        };
+        for (i, cell) in maze.grid.iter().flatten().enumerate() {
+            packed.bits.set(i, cell.is_passable());
+        }
+        packed
+    }
+}
+
+impl std::fmt::Display for PackedMaze {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        grid::write_grid(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    use crate::maze::{ExitPlacement, MazeTrait, generators::Eller};
+
+    // Every open cell reachable from the start, over one fewer opening than there are cells
+    fn is_perfect(packed: &PackedMaze) -> bool {
+        let (x, y) = packed.size();
+        let open: Vec<Pos> = (0..y)
+            .flat_map(|y| (0..x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| packed.get_at(p).is_passable())
+            .collect();
+        let joins: usize = open.iter().map(|&p| packed.open_neighbours(p).len()).sum();
+
+        let mut seen = HashSet::from([packed.start()]);
+        let mut stack = vec![packed.start()];
+        while let Some(p) = stack.pop() {
+            for n in packed.open_neighbours(p) {
+                if seen.insert(n) {
+                    stack.push(n);
+                }
+            }
+        }
+        seen.len() == open.len() && joins / 2 + 1 == open.len()
+    }
+
+    #[test]
+    fn round_trips_through_maze() {
+        let mut maze = Maze::with_seed(31, 21, 9);
+        maze.set_exit_placement(ExitPlacement::Farthest);
+        maze.generate_with(&mut Eller).unwrap();
+        let packed = PackedMaze::from(&maze);
+        assert!(is_perfect(&packed));
+        assert_eq!((packed.start(), packed.exit()), (maze.start(), maze.exit()));
+        assert_eq!(packed.to_string(), maze.to_string());
+        assert_eq!(packed.to_maze().to_string(), maze.to_string());
+    }
+
+    #[test]
+    fn generates_a_perfect_maze() {
+        let mut packed = PackedMaze::with_seed(41, 31, 3);
+        packed.generate();
+        assert!(is_perfect(&packed), "{}", packed);
+
+        let mut again = PackedMaze::with_seed(41, 31, 3);
+        again.generate();
+        assert_eq!(again.to_string(), packed.to_string());
+    }
+
+    #[test]
+    fn start_and_exit_follow_writes() {
+        let mut packed = PackedMaze::with_seed(11, 9, 1);
+        packed.generate();
+        let start = packed.start();
+        let spot = packed.open_neighbours(start)[0];
+        assert_eq!(packed.try_set(spot, Cell::Exit), Ok(Cell::Passage));
+        assert_eq!(packed.exit(), Some(spot));
+        packed.swap_at(start, spot);
+        assert_eq!((packed.start(), packed.exit()), (spot, Some(start)));
+        packed.insert_at(start, Cell::Wall);
+        assert_eq!(packed.exit(), None);
+        assert!(packed.try_get(Pos::new(11, 0)).is_err());
+    }
+}
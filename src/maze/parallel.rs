@@ -0,0 +1,195 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use rand::{Rng as _, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use super::{
+    Cell, Maze, MazeTrait,
+    generators::{GenerateError, RecursiveBacktracker},
+    util::{Sets, splitmix},
+};
+
+pub(super) const NAME: &str = "parallel-backtracker";
+
+impl Maze {
+    // Cuts the lattice into tiles of `tile` by `tile` cells, carves each one as a separate
+    // backtracker maze spread over `threads` workers, then joins the tiles along a random
+    // spanning tree with one gap in each shared wall, which keeps the whole thing perfect.
+    // Every tile's seed comes from the maze seed and the tile's place in the grid, so the
+    // result is the same however many threads did the work.
+    pub fn generate_parallel(&mut self, tile: usize, threads: usize) -> Result<(), GenerateError> {
+        if self.mask.is_some() {
+            return Err(GenerateError::Mask(NAME));
+        }
+        if self.wrap {
+            return Err(GenerateError::Wrap(NAME));
+        }
+        let (w, h) = ((self.x - 1) / 2, (self.y - 1) / 2);
+        let tile = tile.max(1);
+        let (across, down) = (w.div_ceil(tile), h.div_ceil(tile));
+        let base = self.seed.unwrap_or_else(|| self.rng.random());
+        self.grid = vec![vec![Cell::Wall; self.x]; self.y];
+        self.walls.clear();
+
+        // Tiles finish in any order, so each one comes back with its id and is copied in
+        // as soon as it arrives rather than held until the rest are done
+        let next = AtomicUsize::new(0);
+        let (send, receive) = mpsc::channel();
+        thread::scope(|scope| {
+            // More workers than tiles would only sit idle
+            for _ in 0..threads.clamp(1, across * down) {
+                let send = send.clone();
+                let next = &next;
+                scope.spawn(move || {
+                    loop {
+                        let id = next.fetch_add(1, Ordering::Relaxed);
+                        if id >= across * down {
+                            break;
+                        }
+                        let (tx, ty) = (id % across, id / across);
+                        let tw = tile.min(w - tx * tile);
+                        let th = tile.min(h - ty * tile);
+                        let mut part = Maze::with_seed(tw * 2 + 1, th * 2 + 1, derive(base, id));
+                        part.generate_with(&mut RecursiveBacktracker)
+                            .expect("tiles are plain rectangles, which the backtracker carves");
+                        if send.send((id, part)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(send);
+
+            for (id, part) in receive {
+                let (ox, oy) = ((id % across) * tile * 2, (id / across) * tile * 2);
+                for (py, row) in part.grid.iter().enumerate() {
+                    for (px, cell) in row.iter().enumerate() {
+                        if cell.is_passable() {
+                            self.grid[oy + py][ox + px] = Cell::Passage;
+                        }
+                    }
+                }
+            }
+        });
+
+        // Kruskal's over the tiles themselves. Each edge says which way it runs, as with a
+        // single column of tiles the one below is also the next id along.
+        let mut edges = vec![];
+        for ty in 0..down {
+            for tx in 0..across {
+                if tx + 1 < across {
+                    edges.push((ty * across + tx, ty * across + tx + 1, true));
+                }
+                if ty + 1 < down {
+                    edges.push((ty * across + tx, (ty + 1) * across + tx, false));
+                }
+            }
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(derive(base, across * down));
+        edges.shuffle(&mut rng);
+        let mut sets = Sets::new(across * down);
+        for (a, b, horizontal) in edges {
+            if !sets.union(a, b) {
+                continue;
+            }
+            let (tx, ty) = (a % across, a / across);
+            if horizontal {
+                let rows = ty * tile..(ty * tile + tile).min(h);
+                let cy = rng.random_range(rows);
+                self.grid[cy * 2 + 1][(tx + 1) * tile * 2] = Cell::Passage;
+            } else {
+                let cols = tx * tile..(tx * tile + tile).min(w);
+                let cx = rng.random_range(cols);
+                self.grid[(ty + 1) * tile * 2][cx * 2 + 1] = Cell::Passage;
+            }
+        }
+
+        self.finish(NAME);
+        Ok(())
+    }
+}
+
+// Seed for one part of the maze, splitmix64 over the maze seed and the part's index
+fn derive(seed: u64, index: usize) -> u64 {
+    splitmix(seed.wrapping_add((index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::maze::{ExitPlacement, Grid, Mask, Pos};
+
+    // Every open cell reachable from the start, over one fewer opening than there are cells
+    fn is_perfect(maze: &Maze) -> bool {
+        let open: Vec<Pos> = (0..maze.y)
+            .flat_map(|y| (0..maze.x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| maze.get_at(p).is_passable())
+            .collect();
+        let joins: usize = open.iter().map(|&p| maze.open_neighbours(p).len()).sum();
+
+        let mut seen = HashSet::from([maze.start]);
+        let mut stack = vec![maze.start];
+        while let Some(p) = stack.pop() {
+            for n in maze.open_neighbours(p) {
+                if seen.insert(n) {
+                    stack.push(n);
+                }
+            }
+        }
+        seen.len() == open.len() && joins / 2 + 1 == open.len()
+    }
+
+    #[test]
+    fn thread_count_doesnt_change_the_maze() {
+        let mazes: Vec<String> = [1, 3, 8, 100]
+            .iter()
+            .map(|&threads| {
+                let mut maze = Maze::with_seed(81, 51, 12);
+                maze.set_exit_placement(ExitPlacement::Farthest);
+                maze.generate_parallel(7, threads).unwrap();
+                assert!(is_perfect(&maze), "{} threads", threads);
+                maze.to_string()
+            })
+            .collect();
+        assert!(mazes.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+
+    #[test]
+    fn a_single_column_of_tiles_joins_downwards() {
+        for (x, y) in [(15, 81), (13, 41), (81, 15), (3, 3)] {
+            for seed in 0..3 {
+                let mut maze = Maze::with_seed(x, y, seed);
+                maze.generate_parallel(7, 2).unwrap();
+                assert!(is_perfect(&maze), "{}x{} seed {}\n{}", x, y, seed, maze);
+                assert!(
+                    maze.grid[0].iter().all(|c| c.is_wall())
+                        && maze
+                            .grid
+                            .iter()
+                            .all(|row| row[0].is_wall() && row[x - 1].is_wall()),
+                    "{}x{} seed {} opened the border",
+                    x,
+                    y,
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn masks_and_wrapping_are_refused() {
+        let mut maze = Maze::with_mask(Mask::new(5, 5), 1).unwrap();
+        assert_eq!(maze.generate_parallel(2, 2), Err(GenerateError::Mask(NAME)));
+        let mut maze = Maze::with_seed(21, 21, 1);
+        maze.set_wrap(true);
+        assert_eq!(maze.generate_parallel(2, 2), Err(GenerateError::Wrap(NAME)));
+    }
+}
@@ -0,0 +1,134 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::util::Sets;
+
+// Which walls of one row of cells are open. `east[cx]` is the wall to the right of cell cx
+// and `south[cx]` the one below it, so the last entry of `east` and all of `south` on the
+// bottom row are always closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub east: Vec<bool>,
+    pub south: Vec<bool>,
+}
+
+// Eller's, handing out one finished row at a time and keeping nothing but the sets of the row
+// being built. Set ids are renumbered every row so they stay below twice the width, which
+// lets plain vectors stand in for the union-find and per-set tables.
+pub struct EllerRows<R = ChaCha8Rng> {
+    width: usize,
+    height: usize,
+    row: usize,
+    sets: Vec<usize>, // usize::MAX for cells not yet in a set
+    joined: Sets,
+    rename: Vec<usize>,
+    count: Vec<usize>,
+    chosen: Vec<usize>,
+    dropped: Vec<bool>,
+    rng: R,
+}
+
+impl EllerRows {
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Self::with_rng(width, height, ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> EllerRows<R> {
+    pub fn with_rng(width: usize, height: usize, rng: R) -> Self {
+        Self {
+            width,
+            height,
+            row: 0,
+            sets: vec![usize::MAX; width],
+            joined: Sets::new(width * 2),
+            rename: vec![usize::MAX; width * 2],
+            count: vec![0; width * 2],
+            chosen: vec![0; width * 2],
+            dropped: vec![false; width * 2],
+            rng,
+        }
+    }
+
+    // Squeezes the sets carried down from the last row into 0..k and gives the new cells
+    // fresh ids after them
+    fn renumber(&mut self) {
+        let mut next = 0;
+        for cx in 0..self.width {
+            let set = self.sets[cx];
+            if set == usize::MAX {
+                continue;
+            }
+            let root = self.joined.find(set);
+            if self.rename[root] == usize::MAX {
+                self.rename[root] = next;
+                next += 1;
+            }
+            self.sets[cx] = self.rename[root];
+        }
+        for cx in 0..self.width {
+            if self.sets[cx] == usize::MAX {
+                self.sets[cx] = next;
+                next += 1;
+            }
+        }
+        self.joined.reset();
+        self.rename.fill(usize::MAX);
+    }
+}
+
+impl<R: Rng> Iterator for EllerRows<R> {
+    type Item = Row;
+
+    fn next(&mut self) -> Option<Row> {
+        if self.row >= self.height || self.width == 0 {
+            return None;
+        }
+        let last = self.row + 1 == self.height;
+        self.row += 1;
+        self.renumber();
+
+        // Join neighbouring cells of different sets, always on the last row
+        let mut east = vec![false; self.width];
+        for (cx, open) in east.iter_mut().enumerate().take(self.width - 1) {
+            let (a, b) = (self.sets[cx], self.sets[cx + 1]);
+            if self.joined.find(a) != self.joined.find(b) && (last || self.rng.random_bool(0.5)) {
+                *open = true;
+                self.joined.union(a, b);
+            }
+        }
+        let mut south = vec![false; self.width];
+        if last {
+            return Some(Row { east, south });
+        }
+
+        // Every set drops at least one passage into the next row. Each cell drops with even
+        // odds, and a set that got none drops one member picked by reservoir sampling.
+        for (cx, down) in south.iter_mut().enumerate() {
+            let root = self.joined.find(self.sets[cx]);
+            self.sets[cx] = root;
+            self.count[root] += 1;
+            if self.rng.random_range(0..self.count[root]) == 0 {
+                self.chosen[root] = cx;
+            }
+            if self.rng.random_bool(0.5) {
+                *down = true;
+                self.dropped[root] = true;
+            }
+        }
+        for cx in 0..self.width {
+            let root = self.sets[cx];
+            if !self.dropped[root] && self.count[root] > 0 {
+                south[self.chosen[root]] = true;
+            }
+            self.count[root] = 0;
+        }
+        for (cx, &down) in south.iter().enumerate() {
+            self.dropped[self.sets[cx]] = false;
+            if !down {
+                self.sets[cx] = usize::MAX;
+            }
+        }
+        Some(Row { east, south })
+    }
+}
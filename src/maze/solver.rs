@@ -0,0 +1,621 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+use super::{Cell, Grid, Maze, Pos};
+
+// Clockwise from north, so turning left or right is a step around this table
+const HEADINGS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub path: Vec<Pos>, // from the first cell to the goal, both included
+    pub visited: usize, // distinct cells the solver looked at
+}
+
+pub trait Solver {
+    fn name(&self) -> &'static str;
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution>;
+}
+
+fn index(maze: &Maze, pos: Pos) -> usize {
+    pos.y * maze.x + pos.x
+}
+
+fn passable(maze: &Maze, pos: Pos) -> bool {
+    maze.try_get(pos).is_ok_and(Cell::is_passable)
+}
+
+fn step(maze: &Maze, pos: Pos, heading: usize) -> Option<Pos> {
+    let (dx, dy) = HEADINGS[heading];
+    let next = maze.neighbour(pos, dx, dy)?;
+    passable(maze, next).then_some(next)
+}
+
+// Manhattan distance, measured the short way round when the maze wraps
+fn estimate(maze: &Maze, a: Pos, b: Pos) -> usize {
+    if !maze.wraps() {
+        return a.manhattan(b);
+    }
+    let around = |a: usize, b: usize, period: usize| {
+        let d = (a % period).abs_diff(b % period);
+        d.min(period - d)
+    };
+    let (w, h) = maze.period();
+    around(a.x, b.x, w) + around(a.y, b.y, h)
+}
+
+// Walks the predecessor links back from `to`
+fn backtrack(maze: &Maze, prev: &[Option<Pos>], to: Pos) -> Vec<Pos> {
+    let mut path = vec![to];
+    while let Some(p) = prev[index(maze, path[path.len() - 1])] {
+        path.push(p);
+    }
+    path.reverse();
+    path
+}
+
+// Turns a walk into a simple path by cutting out every loop it made
+fn erase_loops(walk: &[Pos]) -> Vec<Pos> {
+    let mut path: Vec<Pos> = vec![];
+    let mut at: HashMap<Pos, usize> = HashMap::new();
+    for &cell in walk {
+        if let Some(&i) = at.get(&cell) {
+            for gone in path.drain(i + 1..) {
+                at.remove(&gone);
+            }
+        } else {
+            at.insert(cell, path.len());
+            path.push(cell);
+        }
+    }
+    path
+}
+
+/// Breadth-first search, always finds a shortest path.
+pub struct Bfs;
+
+impl Solver for Bfs {
+    fn name(&self) -> &'static str {
+        "bfs"
+    }
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution> {
+        if !passable(maze, from) || !passable(maze, to) {
+            return None;
+        }
+        let mut prev = vec![None; maze.x * maze.y];
+        let mut seen = vec![false; maze.x * maze.y];
+        let mut queue = VecDeque::from([from]);
+        seen[index(maze, from)] = true;
+        let mut visited = 0;
+
+        while let Some(cell) = queue.pop_front() {
+            visited += 1;
+            if cell == to {
+                return Some(Solution {
+                    path: backtrack(maze, &prev, to),
+                    visited,
+                });
+            }
+            for n in maze.open_neighbours(cell) {
+                if !seen[index(maze, n)] {
+                    seen[index(maze, n)] = true;
+                    prev[index(maze, n)] = Some(cell);
+                    queue.push_back(n);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Depth-first search, finds a path but not necessarily the shortest one.
+pub struct Dfs;
+
+impl Solver for Dfs {
+    fn name(&self) -> &'static str {
+        "dfs"
+    }
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution> {
+        if !passable(maze, from) || !passable(maze, to) {
+            return None;
+        }
+        let mut prev = vec![None; maze.x * maze.y];
+        let mut seen = vec![false; maze.x * maze.y];
+        let mut stack = vec![from];
+        seen[index(maze, from)] = true;
+        let mut visited = 0;
+
+        while let Some(cell) = stack.pop() {
+            visited += 1;
+            if cell == to {
+                return Some(Solution {
+                    path: backtrack(maze, &prev, to),
+                    visited,
+                });
+            }
+            for n in maze.open_neighbours(cell) {
+                if !seen[index(maze, n)] {
+                    seen[index(maze, n)] = true;
+                    prev[index(maze, n)] = Some(cell);
+                    stack.push(n);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A* with the Manhattan distance as heuristic, shortest path with fewer cells visited than BFS.
+pub struct AStar;
+
+impl Solver for AStar {
+    fn name(&self) -> &'static str {
+        "a-star"
+    }
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution> {
+        if !passable(maze, from) || !passable(maze, to) {
+            return None;
+        }
+        let heuristic = |pos: Pos| estimate(maze, pos, to);
+        let mut prev = vec![None; maze.x * maze.y];
+        let mut cost = vec![usize::MAX; maze.x * maze.y];
+        let mut closed = vec![false; maze.x * maze.y];
+        let mut open = BinaryHeap::from([Reverse((heuristic(from), 0, from))]);
+        cost[index(maze, from)] = 0;
+        let mut visited = 0;
+
+        while let Some(Reverse((_, g, cell))) = open.pop() {
+            if closed[index(maze, cell)] {
+                continue;
+            }
+            closed[index(maze, cell)] = true;
+            visited += 1;
+            if cell == to {
+                return Some(Solution {
+                    path: backtrack(maze, &prev, to),
+                    visited,
+                });
+            }
+            for n in maze.open_neighbours(cell) {
+                if g + 1 < cost[index(maze, n)] {
+                    cost[index(maze, n)] = g + 1;
+                    prev[index(maze, n)] = Some(cell);
+                    open.push(Reverse((g + 1 + heuristic(n), g + 1, n)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Dijkstra over the terrain costs, the cheapest path rather than the one with fewest steps.
+pub struct Dijkstra;
+
+impl Solver for Dijkstra {
+    fn name(&self) -> &'static str {
+        "dijkstra"
+    }
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution> {
+        if !passable(maze, from) || !passable(maze, to) {
+            return None;
+        }
+        let mut prev = vec![None; maze.x * maze.y];
+        let mut cost = vec![usize::MAX; maze.x * maze.y];
+        let mut closed = vec![false; maze.x * maze.y];
+        let mut open = BinaryHeap::from([Reverse((0, from))]);
+        cost[index(maze, from)] = 0;
+        let mut visited = 0;
+
+        while let Some(Reverse((c, cell))) = open.pop() {
+            if closed[index(maze, cell)] {
+                continue;
+            }
+            closed[index(maze, cell)] = true;
+            visited += 1;
+            if cell == to {
+                return Some(Solution {
+                    path: backtrack(maze, &prev, to),
+                    visited,
+                });
+            }
+            for n in maze.open_neighbours(cell) {
+                let through = c + maze.cost(n);
+                if through < cost[index(maze, n)] {
+                    cost[index(maze, n)] = through;
+                    prev[index(maze, n)] = Some(cell);
+                    open.push(Reverse((through, n)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Dead-end filling, plugs every dead end until only the routes between the two cells are left.
+pub struct DeadEndFilling;
+
+impl Solver for DeadEndFilling {
+    fn name(&self) -> &'static str {
+        "dead-end-filling"
+    }
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution> {
+        if !passable(maze, from) || !passable(maze, to) {
+            return None;
+        }
+        let mut filled = vec![false; maze.x * maze.y];
+        let open_count = |cell: Pos, filled: &[bool]| {
+            maze.open_neighbours(cell)
+                .into_iter()
+                .filter(|&n| !filled[index(maze, n)])
+                .count()
+        };
+
+        let mut ends: Vec<Pos> = (0..maze.y)
+            .flat_map(|y| (0..maze.x).map(move |x| Pos::new(x, y)))
+            .filter(|&c| passable(maze, c) && c != from && c != to && open_count(c, &filled) <= 1)
+            .collect();
+        let mut visited = 0;
+
+        // Filling a dead end can turn the cell it led into a dead end as well
+        while let Some(cell) = ends.pop() {
+            if filled[index(maze, cell)] {
+                continue;
+            }
+            filled[index(maze, cell)] = true;
+            visited += 1;
+            for n in maze.open_neighbours(cell) {
+                if !filled[index(maze, n)] && n != from && n != to && open_count(n, &filled) <= 1 {
+                    ends.push(n);
+                }
+            }
+        }
+
+        // What is left is the solution, or several if the maze has loops
+        let mut prev = vec![None; maze.x * maze.y];
+        let mut seen = filled;
+        let mut queue = VecDeque::from([from]);
+        seen[index(maze, from)] = true;
+        while let Some(cell) = queue.pop_front() {
+            visited += 1;
+            if cell == to {
+                return Some(Solution {
+                    path: backtrack(maze, &prev, to),
+                    visited,
+                });
+            }
+            for n in maze.open_neighbours(cell) {
+                if !seen[index(maze, n)] {
+                    seen[index(maze, n)] = true;
+                    prev[index(maze, n)] = Some(cell);
+                    queue.push_back(n);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// Wall follower, keeps one hand on the wall. Only guaranteed to arrive when the goal is
+/// connected to the walls the walk starts against, otherwise it gives up after a full lap.
+pub struct WallFollower {
+    pub hand: Hand,
+}
+
+impl WallFollower {
+    pub fn new(hand: Hand) -> Self {
+        Self { hand }
+    }
+}
+
+impl Solver for WallFollower {
+    fn name(&self) -> &'static str {
+        match self.hand {
+            Hand::Left => "wall-follower-left",
+            Hand::Right => "wall-follower-right",
+        }
+    }
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution> {
+        if !passable(maze, from) || !passable(maze, to) {
+            return None;
+        }
+        // Preferred turns in order: towards the hand, straight on, away from it, back
+        let turns = match self.hand {
+            Hand::Left => [3, 0, 1, 2],
+            Hand::Right => [1, 0, 3, 2],
+        };
+
+        let mut heading = 0;
+        let mut cell = from;
+        let mut walk = vec![from];
+
+        // Head north until there is a wall to put the hand on, then turn away from it. A
+        // wrapping maze can have a column with no wall in it, so only go round once.
+        for _ in 0..maze.y {
+            if cell == to {
+                break;
+            }
+            match step(maze, cell, heading) {
+                Some(next) => {
+                    cell = next;
+                    walk.push(cell);
+                }
+                None => break,
+            }
+        }
+        heading = (heading + turns[2]) % 4;
+
+        // Every (cell, heading) state can only come up once before the walk repeats itself
+        for _ in 0..maze.x * maze.y * 4 {
+            if cell == to {
+                let visited = walk.iter().collect::<HashSet<_>>().len();
+                return Some(Solution {
+                    path: erase_loops(&walk),
+                    visited,
+                });
+            }
+            let (h, next) = turns
+                .iter()
+                .map(|t| (heading + t) % 4)
+                .find_map(|h| step(maze, cell, h).map(|n| (h, n)))?;
+            heading = h;
+            cell = next;
+            walk.push(cell);
+        }
+        None
+    }
+}
+
+/// Trémaux, marks each passage as it walks it and never uses one more than twice.
+pub struct Tremaux;
+
+impl Solver for Tremaux {
+    fn name(&self) -> &'static str {
+        "tremaux"
+    }
+
+    fn solve(&mut self, maze: &Maze, from: Pos, to: Pos) -> Option<Solution> {
+        if !passable(maze, from) || !passable(maze, to) {
+            return None;
+        }
+        // Passages are keyed by the cell indices at both ends, lowest first
+        let key = |a: Pos, b: Pos| {
+            let (a, b) = (index(maze, a), index(maze, b));
+            (a.min(b), a.max(b))
+        };
+        let mut marks: HashMap<(usize, usize), u8> = HashMap::new();
+        let mut seen = vec![false; maze.x * maze.y];
+        let mut previous: Option<Pos> = None;
+        let mut cell = from;
+        let mut walk = vec![from];
+        let mut visited = 0;
+
+        while cell != to {
+            let arrived_fresh = previous.is_some_and(|p| marks.get(&key(p, cell)) == Some(&1));
+            let next = if seen[index(maze, cell)] && arrived_fresh {
+                // Came down a new passage into a known junction, so go back the way we came
+                previous.unwrap()
+            } else {
+                let options = maze.open_neighbours(cell);
+                let least = |n: &Pos| marks.get(&key(cell, *n)).copied().unwrap_or(0);
+                match options
+                    .iter()
+                    .filter(|n| least(n) == 0 && Some(**n) != previous)
+                    .chain(options.iter().filter(|n| least(n) == 1))
+                    .next()
+                {
+                    Some(&n) => n,
+                    None => return None,
+                }
+            };
+
+            if !seen[index(maze, cell)] {
+                seen[index(maze, cell)] = true;
+                visited += 1;
+            }
+            *marks.entry(key(cell, next)).or_insert(0) += 1;
+            previous = Some(cell);
+            cell = next;
+            walk.push(cell);
+        }
+
+        Some(Solution {
+            path: erase_loops(&walk),
+            visited: visited + 1,
+        })
+    }
+}
+
+impl Maze {
+    // Solves from the start cell of the maze
+    pub fn solve_with(&self, solver: &mut dyn Solver, to: Pos) -> Option<Solution> {
+        solver.solve(self, self.start, to)
+    }
+
+    // Solves from the start to the exit, None if the maze has no exit
+    pub fn solve(&self, solver: &mut dyn Solver) -> Option<Solution> {
+        solver.solve(self, self.start, self.exit?)
+    }
+
+    // Step counts from `from` to every reachable cell, indexed [y][x]
+    pub fn distances(&self, from: Pos) -> Vec<Vec<Option<usize>>> {
+        let mut dist = vec![vec![None; self.x]; self.y];
+        if !passable(self, from) {
+            return dist;
+        }
+        dist[from.y][from.x] = Some(0);
+        let mut queue = VecDeque::from([from]);
+        while let Some(cell) = queue.pop_front() {
+            let d = dist[cell.y][cell.x].unwrap();
+            for n in self.open_neighbours(cell) {
+                if dist[n.y][n.x].is_none() {
+                    dist[n.y][n.x] = Some(d + 1);
+                    queue.push_back(n);
+                }
+            }
+        }
+        dist
+    }
+
+    // The reachable cell furthest from `from` along with its distance
+    pub fn farthest(&self, from: Pos) -> (Pos, usize) {
+        let dist = self.distances(from);
+        let mut best = (from, 0);
+        for (y, row) in dist.iter().enumerate() {
+            for (x, d) in row.iter().enumerate() {
+                if let Some(d) = *d
+                    && d > best.1
+                {
+                    best = (Pos::new(x, y), d);
+                }
+            }
+        }
+        best
+    }
+
+    // Marks the path into the grid, leaving the start and anything else that isn't a plain passage
+    pub fn mark_path(&mut self, solution: &Solution) {
+        for &pos in &solution.path {
+            if self.grid[pos.y][pos.x] == Cell::Passage {
+                self.grid[pos.y][pos.x] = Cell::Path;
+            }
+        }
+    }
+
+    pub fn clear_path(&mut self) {
+        for cell in self.grid.iter_mut().flatten() {
+            if *cell == Cell::Path {
+                *cell = Cell::Passage;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{
+        MazeTrait, Terrain,
+        generators::{RecursiveBacktracker, RecursiveDivision},
+        sketch,
+    };
+
+    fn solvers() -> Vec<Box<dyn Solver>> {
+        vec![
+            Box::new(Bfs),
+            Box::new(Dfs),
+            Box::new(AStar),
+            Box::new(Dijkstra),
+            Box::new(DeadEndFilling),
+            Box::new(WallFollower::new(Hand::Left)),
+            Box::new(WallFollower::new(Hand::Right)),
+            Box::new(Tremaux),
+        ]
+    }
+
+    // Every step of the path moves onto a passable neighbour
+    fn assert_walkable(maze: &Maze, path: &[Pos], name: &str) {
+        for pair in path.windows(2) {
+            assert!(
+                (0..HEADINGS.len()).any(|h| step(maze, pair[0], h) == Some(pair[1])),
+                "{} jumps from {} to {}",
+                name,
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    // A perfect maze has a single route, so every solver has to come back with it
+    #[test]
+    fn perfect_mazes_have_one_route() {
+        let goal = Pos::new(29, 29);
+        for seed in 0..10 {
+            let mut maze = Maze::with_seed(31, 31, seed);
+            maze.generate_with(&mut RecursiveBacktracker).unwrap();
+            let expected = maze.solve_with(&mut Bfs, goal).unwrap().path;
+            assert_eq!(expected.first(), Some(&maze.start));
+            assert_eq!(expected.last(), Some(&goal));
+            assert_walkable(&maze, &expected, "bfs");
+            for mut solver in solvers() {
+                let solution = maze.solve_with(solver.as_mut(), goal).unwrap();
+                assert_eq!(solution.path, expected, "{} seed {}", solver.name(), seed);
+            }
+        }
+    }
+
+    #[test]
+    fn shortest_paths_through_rooms() {
+        let goal = Pos::new(29, 29);
+        for seed in 0..10 {
+            let mut maze = Maze::with_seed(31, 31, seed);
+            maze.generate_with(&mut RecursiveDivision::new(2, 0.5))
+                .unwrap();
+            let shortest = maze.solve_with(&mut Bfs, goal).unwrap().path.len();
+            for mut solver in solvers() {
+                let Some(path) = maze.solve_with(solver.as_mut(), goal).map(|s| s.path) else {
+                    // The wall follower can lose its way round a room
+                    assert!(solver.name().starts_with("wall-follower"));
+                    continue;
+                };
+                assert_eq!(path.first(), Some(&maze.start));
+                assert_eq!(path.last(), Some(&goal));
+                assert_walkable(&maze, &path, solver.name());
+                // Without terrain every step costs the same, so the cheapest path is a shortest one too
+                if matches!(
+                    solver.name(),
+                    "bfs" | "a-star" | "dijkstra" | "dead-end-filling"
+                ) {
+                    assert_eq!(path.len(), shortest, "{} seed {}", solver.name(), seed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dijkstra_takes_the_long_road_round_the_water() {
+        let mut maze = sketch(
+            "
+            #########
+            #S.....E#
+            #.#####.#
+            #.......#
+            #########
+            ",
+        );
+        for x in 2..=6 {
+            maze.set_terrain(Pos::new(x, 1), Terrain::Water).unwrap();
+        }
+        for (x, y) in [(1, 2), (1, 3), (7, 2), (7, 3)]
+            .into_iter()
+            .chain((2..=6).map(|x| (x, 3)))
+        {
+            maze.set_terrain(Pos::new(x, y), Terrain::Road).unwrap();
+        }
+        let exit = maze.exit().unwrap();
+
+        let short = maze.solve_with(&mut Bfs, exit).unwrap().path;
+        assert_eq!(short.len(), 7);
+        assert_eq!(maze.path_cost(&short), 5 * 9 + 2);
+
+        let cheap = maze.solve_with(&mut Dijkstra, exit).unwrap().path;
+        assert_eq!(cheap.len(), 11);
+        assert_eq!(maze.path_cost(&cheap), 9 + 2);
+        assert!(cheap.iter().all(|p| p.y != 1 || p.x == 1 || p.x == 7));
+        assert_walkable(&maze, &cheap, "dijkstra");
+    }
+}
@@ -0,0 +1,102 @@
+use super::{Generator, Maze, Pos, generators::Prim};
+
+// One wall knocked out by Prim's, along with the walls still waiting on the frontier
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub carved: Pos,
+    pub frontier: Vec<Pos>,
+}
+
+// Runs `Maze::generate` one carved cell at a time. The first step is the start cell, and once
+// the frontier runs out the start and exit are marked, leaving the same maze a plain
+// `generate` would have made from the same seed.
+pub struct Generation<'a> {
+    maze: &'a mut Maze,
+    started: bool,
+    done: bool,
+}
+
+impl Generation<'_> {
+    // The maze as carved so far
+    pub fn maze(&self) -> &Maze {
+        self.maze
+    }
+
+    fn frontier(&self) -> Vec<Pos> {
+        self.maze.walls.iter().map(|&w| Pos::from(w)).collect()
+    }
+}
+
+impl Iterator for Generation<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        if self.done {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            self.maze.prepare();
+            Prim::begin(self.maze);
+            return Some(Step {
+                carved: self.maze.start,
+                frontier: self.frontier(),
+            });
+        }
+        match Prim::step(self.maze) {
+            Some(carved) => Some(Step {
+                carved,
+                frontier: self.frontier(),
+            }),
+            None => {
+                self.done = true;
+                self.maze.finish(Prim.name());
+                None
+            }
+        }
+    }
+}
+
+impl Maze {
+    pub fn generate_steps(&mut self) -> Generation<'_> {
+        Generation {
+            maze: self,
+            started: false,
+            done: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{ExitPlacement, MazeTrait};
+
+    #[test]
+    fn steps_end_in_the_generated_maze() {
+        for (x, y) in [(21, 21), (31, 15)] {
+            for seed in 0..3 {
+                let mut stepped = Maze::with_seed(x, y, seed);
+                let mut generated = Maze::with_seed(x, y, seed);
+                stepped.set_exit_placement(ExitPlacement::Farthest);
+                generated.set_exit_placement(ExitPlacement::Farthest);
+
+                let steps: Vec<Step> = stepped.generate_steps().collect();
+                generated.generate();
+                assert_eq!(stepped.to_string(), generated.to_string(), "seed {}", seed);
+                assert_eq!(stepped.exit(), generated.exit());
+                assert_eq!(stepped.algorithm(), "prim");
+
+                // Every step opens one more cell, starting from the start
+                assert_eq!(steps[0].carved, stepped.start());
+                let open = stepped
+                    .to_bytes()
+                    .iter()
+                    .flatten()
+                    .filter(|&&b| b != 0)
+                    .count();
+                assert_eq!(steps.len(), open);
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::io::{self, Write};
+
+use super::{Cell, rows::EllerRows};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    // Two characters a cell with `\r\n` line ends, the same as `Display` for `Maze`
+    #[default]
+    Text,
+    // Raw PBM (P4), one bit a cell with walls black and each line padded to a whole byte
+    Pbm,
+}
+
+// Writes an `x` by `y` grid maze made with Eller's straight to `out`, one grid line at a time,
+// so only a couple of lines are ever held in memory however tall it gets. The start is the
+// top left cell and the exit the bottom right one.
+pub fn write_eller<W: Write>(
+    out: &mut W,
+    x: usize,
+    y: usize,
+    seed: u64,
+    format: Format,
+) -> io::Result<()> {
+    let (w, h) = ((x - 1) / 2, (y - 1) / 2);
+    if format == Format::Pbm {
+        write!(out, "P4\n{} {}\n", x, y)?;
+    }
+    let mut line = vec![Cell::Wall; x];
+    write_line(out, &line, format)?;
+
+    for (cy, row) in EllerRows::new(w, h, seed).enumerate() {
+        line.fill(Cell::Wall);
+        for cx in 0..w {
+            line[cx * 2 + 1] = Cell::Passage;
+            if row.east[cx] {
+                line[cx * 2 + 2] = Cell::Passage;
+            }
+        }
+        if cy == 0 {
+            line[1] = Cell::Start;
+        }
+        if cy + 1 == h && (w > 1 || h > 1) {
+            line[w * 2 - 1] = Cell::Exit;
+        }
+        write_line(out, &line, format)?;
+
+        line.fill(Cell::Wall);
+        for cx in 0..w {
+            if row.south[cx] {
+                line[cx * 2 + 1] = Cell::Passage;
+            }
+        }
+        write_line(out, &line, format)?;
+    }
+
+    // An odd number of grid lines leaves one wall line short, same as `Maze` does
+    if y > h * 2 + 1 {
+        line.fill(Cell::Wall);
+        write_line(out, &line, format)?;
+    }
+    out.flush()
+}
+
+fn write_line<W: Write>(out: &mut W, line: &[Cell], format: Format) -> io::Result<()> {
+    match format {
+        Format::Text => {
+            let mut text = String::with_capacity(line.len() * 2 + 2);
+            for cell in line {
+                text.push_str(cell.symbol());
+            }
+            text.push_str("\r\n");
+            out.write_all(text.as_bytes())
+        }
+        Format::Pbm => {
+            let mut bytes = vec![0u8; line.len().div_ceil(8)];
+            for (i, cell) in line.iter().enumerate() {
+                if cell.is_wall() {
+                    bytes[i / 8] |= 0x80 >> (i % 8);
+                }
+            }
+            out.write_all(&bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::maze::{Mask, Maze, Pos};
+
+    fn streamed(x: usize, y: usize, seed: u64, format: Format) -> Vec<u8> {
+        let mut out = vec![];
+        write_eller(&mut out, x, y, seed, format).unwrap();
+        out
+    }
+
+    // Walls of the text output, one row a line
+    fn walls(text: &[u8]) -> Vec<Vec<bool>> {
+        String::from_utf8(text.to_vec())
+            .unwrap()
+            .split_terminator("\r\n")
+            .map(|line| line.as_bytes().chunks(2).map(|c| c == b"##").collect())
+            .collect()
+    }
+
+    #[test]
+    fn pbm_has_the_same_walls_as_text() {
+        for (x, y) in [(21, 15), (20, 14), (3, 3)] {
+            let text = walls(&streamed(x, y, 4, Format::Text));
+            let mask = Mask::from_pbm(&streamed(x, y, 4, Format::Pbm)).unwrap();
+            assert_eq!(mask.size(), (x, y));
+            assert_eq!(text.len(), y);
+            for (gy, row) in text.iter().enumerate() {
+                assert_eq!(row.len(), x);
+                for (gx, &wall) in row.iter().enumerate() {
+                    assert_eq!(mask.get(gx, gy), wall, "({}, {}) in {}x{}", gx, gy, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn streams_a_perfect_maze() {
+        for seed in 0..3 {
+            let text = streamed(31, 21, seed, Format::Text);
+            let lines: Vec<&str> = std::str::from_utf8(&text).unwrap().lines().collect();
+            assert_eq!(&lines[1][2..4], "[]");
+            assert_eq!(&lines[19][58..60], "<>");
+            let walls = walls(&text);
+            let open = |p: Pos| !walls[p.y][p.x];
+            let cells: Vec<Pos> = (0..21)
+                .flat_map(|y| (0..31).map(move |x| Pos::new(x, y)))
+                .filter(|&p| open(p))
+                .collect();
+            let joins = cells
+                .iter()
+                .filter(|p| p.x + 1 < 31 && open(Pos::new(p.x + 1, p.y)))
+                .count()
+                + cells
+                    .iter()
+                    .filter(|p| p.y + 1 < 21 && open(Pos::new(p.x, p.y + 1)))
+                    .count();
+
+            let mut seen = HashSet::from([Pos::new(1, 1)]);
+            let mut stack = vec![Pos::new(1, 1)];
+            while let Some(p) = stack.pop() {
+                for n in [
+                    Pos::new(p.x + 1, p.y),
+                    Pos::new(p.x - 1, p.y),
+                    Pos::new(p.x, p.y + 1),
+                    Pos::new(p.x, p.y - 1),
+                ] {
+                    if open(n) && seen.insert(n) {
+                        stack.push(n);
+                    }
+                }
+            }
+            assert_eq!(seen.len(), cells.len(), "seed {}", seed);
+            assert_eq!(joins + 1, cells.len(), "seed {}", seed);
+            assert_eq!(streamed(31, 21, seed, Format::Text), text);
+        }
+    }
+
+    #[test]
+    fn text_loads_back_as_a_valid_maze() {
+        for (x, y) in [(31, 21), (20, 14)] {
+            let text = String::from_utf8(streamed(x, y, 7, Format::Text)).unwrap();
+            let maze = Maze::from_text(&text).unwrap();
+            assert_eq!((maze.x, maze.y), (x, y));
+            assert_eq!(maze.validate(), vec![]);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+
+use rand::Rng as _;
+
+use super::{Cell, Grid, Maze, OutOfBounds, Pos};
+
+// What the floor of a cell is made of. Terrain sits under the cells rather than in them, so
+// marking a path or placing an item doesn't wash it away, and walls just ignore theirs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Terrain {
+    Road,
+    #[default]
+    Ground,
+    Mud,
+    Water,
+}
+
+impl Terrain {
+    // Cost of stepping onto a cell
+    pub fn cost(self) -> usize {
+        match self {
+            Terrain::Road => 1,
+            Terrain::Ground => 2,
+            Terrain::Mud => 5,
+            Terrain::Water => 9,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Terrain::Road => "==",
+            Terrain::Ground => "__",
+            Terrain::Mud => ";;",
+            Terrain::Water => "~~",
+        }
+    }
+}
+
+impl std::fmt::Display for Terrain {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl Maze {
+    pub fn terrain_at(&self, pos: Pos) -> Terrain {
+        self.terrain
+            .as_ref()
+            .and_then(|t| t.get(pos.y)?.get(pos.x).copied())
+            .unwrap_or_default()
+    }
+
+    // Returns the terrain that was replaced
+    pub fn set_terrain(&mut self, pos: Pos, terrain: Terrain) -> Result<Terrain, OutOfBounds> {
+        self.check(pos)?;
+        let (x, y) = (self.x, self.y);
+        let layer = self
+            .terrain
+            .get_or_insert_with(|| vec![vec![Terrain::default(); x]; y]);
+        Ok(std::mem::replace(&mut layer[pos.y][pos.x], terrain))
+    }
+
+    // Back to plain ground everywhere
+    pub fn clear_terrain(&mut self) {
+        self.terrain = None;
+    }
+
+    pub fn cost(&self, pos: Pos) -> usize {
+        self.terrain_at(pos).cost()
+    }
+
+    // Summed cost of every step along a path, the first cell is where it starts so it's free
+    pub fn path_cost(&self, path: &[Pos]) -> usize {
+        path.iter().skip(1).map(|&p| self.cost(p)).sum()
+    }
+
+    // Grows random patches of mud, water and road over the passages until about `coverage`
+    // of them is something other than ground. Patches spread along the passages, so roads
+    // and swamps follow the corridors instead of being cut off by walls.
+    pub fn scatter_terrain(&mut self, coverage: f64) {
+        let passages: Vec<Pos> = (0..self.y)
+            .flat_map(|y| (0..self.x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| self.grid[p.y][p.x].is_passable())
+            .collect();
+        let target = (passages.len() as f64 * coverage.clamp(0.0, 1.0)).round() as usize;
+        let mut covered = 0;
+        let mut attempts = 0;
+
+        while covered < target && attempts < passages.len() {
+            attempts += 1;
+            let seed = passages[self.rng.random_range(0..passages.len())];
+            if self.terrain_at(seed) != Terrain::Ground {
+                continue;
+            }
+            let kind = [Terrain::Road, Terrain::Mud, Terrain::Water][self.rng.random_range(0..3)];
+            let size = self.rng.random_range(3..=12).min(target - covered);
+
+            let mut queue = VecDeque::from([seed]);
+            let mut grown = 0;
+            while let Some(p) = queue.pop_front() {
+                if grown == size {
+                    break;
+                }
+                if self.terrain_at(p) != Terrain::Ground {
+                    continue;
+                }
+                self.set_terrain(p, kind).unwrap();
+                grown += 1;
+                for n in self.open_neighbours(p) {
+                    if self.rng.random_bool(0.8) {
+                        queue.push_back(n);
+                    }
+                }
+            }
+            covered += grown;
+        }
+    }
+
+    // Like Display, but plain passages show what they are made of
+    pub fn render_terrain(&self) -> String {
+        let mut out = String::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                if cell == Cell::Passage {
+                    out.push_str(self.terrain_at(Pos::new(x, y)).symbol());
+                } else {
+                    out.push_str(cell.symbol());
+                }
+            }
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{MazeTrait, generators::RecursiveBacktracker, sketch};
+
+    fn covered(maze: &Maze) -> Vec<Pos> {
+        let (x, y) = maze.size();
+        (0..y)
+            .flat_map(|y| (0..x).map(move |x| Pos::new(x, y)))
+            .filter(|&p| maze.terrain_at(p) != Terrain::Ground)
+            .collect()
+    }
+
+    #[test]
+    fn scatter_stays_on_the_passages() {
+        for seed in 0..5 {
+            let mut maze = Maze::with_seed(31, 21, seed);
+            maze.generate_with(&mut RecursiveBacktracker).unwrap();
+            let passages = maze
+                .grid
+                .iter()
+                .flatten()
+                .filter(|c| c.is_passable())
+                .count();
+            maze.scatter_terrain(0.3);
+
+            let covered = covered(&maze);
+            let target = (passages as f64 * 0.3).round() as usize;
+            assert!(covered.len() <= target, "seed {}", seed);
+            assert!(
+                covered.len() >= target / 2,
+                "seed {} covered {}",
+                seed,
+                covered.len()
+            );
+            assert!(covered.iter().all(|&p| maze.get_at(p).is_passable()));
+        }
+    }
+
+    #[test]
+    fn scatter_is_seeded() {
+        let scattered = |seed| {
+            let mut maze = Maze::with_seed(31, 21, seed);
+            maze.generate();
+            maze.scatter_terrain(0.5);
+            maze.render_terrain()
+        };
+        assert_eq!(scattered(3), scattered(3));
+
+        let mut maze = Maze::with_seed(31, 21, 3);
+        maze.generate();
+        maze.scatter_terrain(0.0);
+        assert!(covered(&maze).is_empty());
+    }
+
+    #[test]
+    fn renders_terrain_under_passages_only() {
+        let mut maze = sketch(
+            "
+            #####
+            #S.E#
+            #####
+            ",
+        );
+        maze.set_terrain(Pos::new(1, 1), Terrain::Mud).unwrap();
+        maze.set_terrain(Pos::new(2, 1), Terrain::Water).unwrap();
+        maze.set_terrain(Pos::new(0, 0), Terrain::Road).unwrap();
+        assert_eq!(
+            maze.render_terrain(),
+            "##########\r\n##[]~~<>##\r\n##########\r\n"
+        );
+        assert_eq!(maze.cost(Pos::new(2, 1)), 9);
+        assert_eq!(
+            maze.set_terrain(Pos::new(2, 1), Terrain::Road),
+            Ok(Terrain::Water)
+        );
+        assert!(maze.set_terrain(Pos::new(5, 0), Terrain::Road).is_err());
+
+        maze.clear_terrain();
+        assert_eq!(
+            maze.render_terrain(),
+            "##########\r\n##[]__<>##\r\n##########\r\n"
+        );
+    }
+}
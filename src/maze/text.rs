@@ -0,0 +1,310 @@
+use std::{fmt, fs, io, path::Path};
+
+use super::{Cell, Grid, Maze, Pos};
+
+// Plain text mazes. A file is a few header lines followed by the grid exactly as `Display`
+// prints it, two characters a cell:
+//
+//     size 7 5
+//     seed 8012
+//     algorithm backtracker
+//     wrap no
+//     start 1 1
+//     exit 5 3
+//     ##############
+//     ##[]________##
+//     ##########__##
+//     ##________<>##
+//     ##############
+//
+// `seed`, `start` and `exit` may be `none`, `start` is only given when its cell is drawn.
+// `wrap` is `yes` or `no`. Every header line is optional, so bare `Display` output
+// loads too, with the start and exit found from the `[]` and `<>` cells. Lines may end in
+// `\n` or `\r\n`, and blank lines around the header and grid are skipped.
+
+#[derive(Debug)]
+pub enum TextError {
+    Io(io::Error),
+    // Lines are counted from 1
+    Header {
+        line: usize,
+        message: String,
+    },
+    Symbol {
+        line: usize,
+        column: usize,
+        symbol: String,
+    },
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    Empty,
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextError::Io(e) => write!(f, "could not read maze: {}", e),
+            TextError::Header { line, message } => write!(f, "line {}: {}", line, message),
+            TextError::Symbol {
+                line,
+                column,
+                symbol,
+            } => write!(
+                f,
+                "line {}, column {}: unknown cell {:?}",
+                line, column, symbol
+            ),
+            TextError::Ragged {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: row is {} cells wide, expected {}",
+                line, found, expected
+            ),
+            TextError::Empty => write!(f, "maze has no grid"),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+impl From<io::Error> for TextError {
+    fn from(e: io::Error) -> Self {
+        TextError::Io(e)
+    }
+}
+
+#[derive(Default)]
+struct Header {
+    // The size and positions keep the line they were on for checking against the grid
+    size: Option<(usize, (usize, usize))>,
+    seed: Option<Option<u64>>,
+    algorithm: Option<String>,
+    wrap: Option<bool>,
+    start: Option<(usize, Option<Pos>)>,
+    exit: Option<(usize, Option<Pos>)>,
+}
+
+impl Maze {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("size {} {}\r\n", self.x, self.y);
+        match self.seed {
+            Some(seed) => text.push_str(&format!("seed {}\r\n", seed)),
+            None => text.push_str("seed none\r\n"),
+        }
+        text.push_str(&format!("algorithm {}\r\n", self.algorithm));
+        text.push_str(if self.wrap {
+            "wrap yes\r\n"
+        } else {
+            "wrap no\r\n"
+        });
+        // An ungenerated or edited maze may not have its start drawn
+        if self.try_get(self.start).is_ok_and(marks_start) {
+            text.push_str(&format!("start {} {}\r\n", self.start.x, self.start.y));
+        } else {
+            text.push_str("start none\r\n");
+        }
+        match self.exit {
+            Some(exit) => text.push_str(&format!("exit {} {}\r\n", exit.x, exit.y)),
+            None => text.push_str("exit none\r\n"),
+        }
+        text.push_str(&self.to_string());
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, TextError> {
+        let mut header = Header::default();
+        let mut rows: Vec<Vec<u8>> = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if rows.is_empty() && read_header(&mut header, line, line_no)? {
+                continue;
+            }
+
+            let chars: Vec<char> = line.chars().collect();
+            let mut row = Vec::with_capacity(chars.len() / 2);
+            for (n, pair) in chars.chunks(2).enumerate() {
+                let symbol: String = pair.iter().collect();
+                let Some(cell) = Cell::from_symbol(&symbol) else {
+                    return Err(TextError::Symbol {
+                        line: line_no,
+                        column: n * 2 + 1,
+                        symbol,
+                    });
+                };
+                row.push(cell.into());
+            }
+            if let Some(first) = rows.first()
+                && first.len() != row.len()
+            {
+                return Err(TextError::Ragged {
+                    line: line_no,
+                    expected: first.len(),
+                    found: row.len(),
+                });
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(TextError::Empty);
+        }
+
+        let mut maze = Maze::from_bytes(&rows);
+        if let Some((line, size)) = header.size
+            && size != maze.size()
+        {
+            return Err(header_error(
+                line,
+                format!(
+                    "size says {}x{} but the grid is {}x{}",
+                    size.0, size.1, maze.x, maze.y
+                ),
+            ));
+        }
+        if let Some(seed) = header.seed {
+            maze.seed = seed;
+        }
+        if let Some(algorithm) = header.algorithm {
+            maze.algorithm = algorithm.into();
+        }
+        if let Some(wrap) = header.wrap {
+            maze.wrap = wrap;
+        }
+        if let Some((line, Some(start))) = header.start {
+            if !maze.try_get(start).is_ok_and(marks_start) {
+                return Err(header_error(line, format!("no start cell at {}", start)));
+            }
+            maze.start = start;
+        }
+        if let Some((line, exit)) = header.exit {
+            if let Some(pos) = exit
+                && maze.try_get(pos).ok() != Some(Cell::Exit)
+            {
+                return Err(header_error(line, format!("no exit cell at {}", pos)));
+            }
+            maze.exit = exit;
+        }
+        Ok(maze)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TextError> {
+        Self::from_text(&fs::read_to_string(path)?)
+    }
+}
+
+// Floors of a `Maze3d` other than the start's begin on the stairs that lead into them
+fn marks_start(cell: Cell) -> bool {
+    cell == Cell::Start || cell.is_stairs()
+}
+
+// False when the line isn't a header line, which means the grid has started
+fn read_header(header: &mut Header, line: &str, line_no: usize) -> Result<bool, TextError> {
+    let mut words = line.split_whitespace();
+    let key = words.next().unwrap_or_default();
+    let values: Vec<&str> = words.collect();
+    let number = |s: &str| {
+        s.parse::<usize>()
+            .map_err(|_| header_error(line_no, format!("{:?} is not a number", s)))
+    };
+    let pair = |values: &[&str]| match values {
+        [a, b] => Ok((number(a)?, number(b)?)),
+        _ => Err(header_error(line_no, format!("{} takes two numbers", key))),
+    };
+
+    match (key, values.as_slice()) {
+        ("size", values) => header.size = Some((line_no, pair(values)?)),
+        ("seed", ["none"]) => header.seed = Some(None),
+        ("seed", [seed]) => {
+            let seed = seed
+                .parse()
+                .map_err(|_| header_error(line_no, format!("{:?} is not a seed", seed)))?;
+            header.seed = Some(Some(seed));
+        }
+        ("algorithm", [name]) => header.algorithm = Some(name.to_string()),
+        ("wrap", ["yes"]) => header.wrap = Some(true),
+        ("wrap", ["no"]) => header.wrap = Some(false),
+        ("wrap", _) => return Err(header_error(line_no, "wrap takes yes or no".to_string())),
+        ("start", ["none"]) => header.start = Some((line_no, None)),
+        ("start", values) => header.start = Some((line_no, Some(Pos::from(pair(values)?)))),
+        ("exit", ["none"]) => header.exit = Some((line_no, None)),
+        ("exit", values) => header.exit = Some((line_no, Some(Pos::from(pair(values)?)))),
+        ("seed" | "algorithm", _) => {
+            return Err(header_error(line_no, format!("{} takes one value", key)));
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn header_error(line: usize, message: String) -> TextError {
+    TextError::Header { line, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{
+        ExitPlacement, MazeTrait, generators, generators::Prim, levels::Maze3d, solver::Bfs,
+    };
+
+    fn round_trip(maze: &Maze) -> Maze {
+        let loaded = Maze::from_text(&maze.to_text()).unwrap();
+        assert_eq!(loaded.to_text(), maze.to_text());
+        loaded
+    }
+
+    #[test]
+    fn ungenerated_maze_has_no_start() {
+        let maze = Maze::with_seed(9, 7, 4);
+        assert!(maze.to_text().contains("start none\r\n"));
+        round_trip(&maze);
+    }
+
+    #[test]
+    fn upper_floors_round_trip() {
+        let mut maze = Maze3d::new(11, 9, 3, 5);
+        maze.generate();
+        for z in 0..3 {
+            let loaded = round_trip(maze.floor(z));
+            assert_eq!(loaded.start(), maze.floor(z).start());
+        }
+    }
+
+    #[test]
+    fn wrapped_maze_keeps_its_tunnels() {
+        let mut maze = Maze::with_seed(21, 15, 2);
+        maze.set_wrap(true);
+        maze.set_exit_placement(ExitPlacement::Farthest);
+        maze.generate_with(&mut Prim).unwrap();
+        let loaded = round_trip(&maze);
+        assert!(loaded.wraps());
+        assert_eq!(loaded.validate(), vec![]);
+        assert!(loaded.solve(&mut Bfs).is_some());
+    }
+
+    #[test]
+    fn any_algorithm_name_loads() {
+        let generators = generators::all();
+        let names = generators
+            .iter()
+            .map(|g| g.name())
+            .chain(["none", "my-own"]);
+        for name in names {
+            let text = format!("algorithm {}\r\n####\r\n", name);
+            assert_eq!(Maze::from_text(&text).unwrap().algorithm(), name);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::VecDeque;
+
+use rand::{Rng as _, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use super::util::Sets;
+
+pub mod hex;
+pub mod polar;
+pub mod triangle;
+
+pub use hex::Hex;
+pub use polar::Polar;
+pub use triangle::Triangle;
+
+// A layout of cells and which of them touch. The square `Maze` grid stores walls as cells,
+// so layouts whose cells aren't squares keep their passages as links between cell ids instead.
+pub trait Topology {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize>;
+
+    // `marker` puts a character inside a cell, e.g. for the start and exit
+    fn render(&self, maze: &GraphMaze<Self>, marker: &dyn Fn(usize) -> Option<char>) -> String
+    where
+        Self: Sized;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Carve {
+    Backtracker,
+    Prim,
+    Kruskal,
+    AldousBroder,
+    Wilson,
+}
+
+impl Carve {
+    pub fn name(self) -> &'static str {
+        match self {
+            Carve::Backtracker => "backtracker",
+            Carve::Prim => "prim",
+            Carve::Kruskal => "kruskal",
+            Carve::AldousBroder => "aldous-broder",
+            Carve::Wilson => "wilson",
+        }
+    }
+}
+
+pub struct GraphMaze<T: Topology> {
+    topology: T,
+    links: Vec<Vec<usize>>,
+    start: usize,
+    exit: Option<usize>,
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl<T: Topology> GraphMaze<T> {
+    pub fn new(topology: T, seed: u64) -> Self {
+        let links = vec![vec![]; topology.len()];
+        Self {
+            topology,
+            links,
+            start: 0,
+            exit: None,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn topology(&self) -> &T {
+        &self.topology
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn set_start(&mut self, cell: usize) {
+        self.start = cell;
+    }
+
+    pub fn exit(&self) -> Option<usize> {
+        self.exit
+    }
+
+    pub fn set_exit(&mut self, cell: Option<usize>) {
+        self.exit = cell;
+    }
+
+    pub fn linked(&self, a: usize, b: usize) -> bool {
+        self.links[a].contains(&b)
+    }
+
+    pub fn links(&self, cell: usize) -> &[usize] {
+        &self.links[cell]
+    }
+
+    fn link(&mut self, a: usize, b: usize) {
+        self.links[a].push(b);
+        self.links[b].push(a);
+    }
+
+    pub fn generate(&mut self) {
+        self.generate_with(Carve::Backtracker);
+    }
+
+    // Carves a perfect maze from the start and puts the exit on the cell furthest from it
+    pub fn generate_with(&mut self, carve: Carve) {
+        self.links = vec![vec![]; self.topology.len()];
+        if self.topology.is_empty() {
+            return;
+        }
+        match carve {
+            Carve::Backtracker => self.backtracker(),
+            Carve::Prim => self.prim(),
+            Carve::Kruskal => self.kruskal(),
+            Carve::AldousBroder => self.aldous_broder(),
+            Carve::Wilson => self.wilson(),
+        }
+        let dist = self.distances(self.start);
+        self.exit = (0..dist.len())
+            .max_by_key(|&c| dist[c])
+            .filter(|&c| c != self.start);
+    }
+
+    // The same algorithms as in `generators`, but those knock wall cells out of a square grid
+    // and follow its mask, while these only have the topology's neighbour lists to go on
+    fn backtracker(&mut self) {
+        let mut visited = vec![false; self.topology.len()];
+        visited[self.start] = true;
+        let mut stack = vec![self.start];
+        while let Some(&cell) = stack.last() {
+            let next: Vec<usize> = self
+                .topology
+                .neighbours(cell)
+                .into_iter()
+                .filter(|&n| !visited[n])
+                .collect();
+            if next.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let n = next[self.rng.random_range(0..next.len())];
+            visited[n] = true;
+            self.link(cell, n);
+            stack.push(n);
+        }
+    }
+
+    fn prim(&mut self) {
+        let mut visited = vec![false; self.topology.len()];
+        visited[self.start] = true;
+        let mut frontier: Vec<(usize, usize)> = self
+            .topology
+            .neighbours(self.start)
+            .into_iter()
+            .map(|n| (self.start, n))
+            .collect();
+        while !frontier.is_empty() {
+            let (from, to) = frontier.swap_remove(self.rng.random_range(0..frontier.len()));
+            if visited[to] {
+                continue;
+            }
+            visited[to] = true;
+            self.link(from, to);
+            for n in self.topology.neighbours(to) {
+                if !visited[n] {
+                    frontier.push((to, n));
+                }
+            }
+        }
+    }
+
+    fn kruskal(&mut self) {
+        let mut edges = vec![];
+        for cell in 0..self.topology.len() {
+            for n in self.topology.neighbours(cell) {
+                if cell < n {
+                    edges.push((cell, n));
+                }
+            }
+        }
+        edges.shuffle(&mut self.rng);
+
+        let mut sets = Sets::new(self.topology.len());
+        for (a, b) in edges {
+            if sets.union(a, b) {
+                self.link(a, b);
+            }
+        }
+    }
+
+    fn aldous_broder(&mut self) {
+        let mut visited = vec![false; self.topology.len()];
+        visited[self.start] = true;
+        let mut remaining = self.topology.len() - 1;
+        let mut cell = self.start;
+        while remaining > 0 {
+            let next = self.topology.neighbours(cell);
+            let n = next[self.rng.random_range(0..next.len())];
+            if !visited[n] {
+                visited[n] = true;
+                self.link(cell, n);
+                remaining -= 1;
+            }
+            cell = n;
+        }
+    }
+
+    fn wilson(&mut self) {
+        let mut visited = vec![false; self.topology.len()];
+        visited[self.start] = true;
+        let mut exits = vec![usize::MAX; self.topology.len()];
+        let mut unvisited: Vec<usize> = (0..self.topology.len()).filter(|&c| !visited[c]).collect();
+
+        while !unvisited.is_empty() {
+            let first = unvisited[self.rng.random_range(0..unvisited.len())];
+            let mut cell = first;
+            while !visited[cell] {
+                let next = self.topology.neighbours(cell);
+                exits[cell] = next[self.rng.random_range(0..next.len())];
+                cell = exits[cell];
+            }
+            let mut cell = first;
+            while !visited[cell] {
+                visited[cell] = true;
+                self.link(cell, exits[cell]);
+                cell = exits[cell];
+            }
+            unvisited.retain(|&c| !visited[c]);
+        }
+    }
+
+    // Steps from `from` to every cell, usize::MAX where it can't be reached
+    pub fn distances(&self, from: usize) -> Vec<usize> {
+        let mut dist = vec![usize::MAX; self.topology.len()];
+        dist[from] = 0;
+        let mut queue = VecDeque::from([from]);
+        while let Some(cell) = queue.pop_front() {
+            for &n in &self.links[cell] {
+                if dist[n] == usize::MAX {
+                    dist[n] = dist[cell] + 1;
+                    queue.push_back(n);
+                }
+            }
+        }
+        dist
+    }
+
+    // Shortest path between two cells along the carved passages
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let dist = self.distances(to);
+        if dist[from] == usize::MAX {
+            return None;
+        }
+        let mut path = vec![from];
+        let mut cell = from;
+        while cell != to {
+            cell = *self.links[cell]
+                .iter()
+                .find(|&&n| dist[n] + 1 == dist[cell])?;
+            path.push(cell);
+        }
+        Some(path)
+    }
+
+    pub fn solve(&self) -> Option<Vec<usize>> {
+        self.path(self.start, self.exit?)
+    }
+
+    // Renders with the start, exit and, if given, a path marked in
+    pub fn render_path(&self, path: &[usize]) -> String {
+        self.topology.render(self, &|cell| {
+            if cell == self.start {
+                Some('S')
+            } else if Some(cell) == self.exit {
+                Some('E')
+            } else if path.contains(&cell) {
+                Some('.')
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl<T: Topology> std::fmt::Display for GraphMaze<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render_path(&[]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARVES: [Carve; 5] = [
+        Carve::Backtracker,
+        Carve::Prim,
+        Carve::Kruskal,
+        Carve::AldousBroder,
+        Carve::Wilson,
+    ];
+
+    // A perfect maze is a spanning tree: one link fewer than cells and every cell reachable
+    fn assert_perfect<T: Topology + Clone>(topology: T) {
+        for carve in CARVES {
+            for seed in 0..3 {
+                let mut maze = GraphMaze::new(topology.clone(), seed);
+                maze.generate_with(carve);
+                let links: usize = (0..topology.len()).map(|c| maze.links(c).len()).sum();
+                assert_eq!(
+                    links / 2,
+                    topology.len() - 1,
+                    "{} seed {}",
+                    carve.name(),
+                    seed
+                );
+                assert!(
+                    maze.distances(maze.start())
+                        .iter()
+                        .all(|&d| d != usize::MAX),
+                    "{} seed {}",
+                    carve.name(),
+                    seed
+                );
+                for c in 0..topology.len() {
+                    for &n in maze.links(c) {
+                        assert!(
+                            topology.neighbours(c).contains(&n),
+                            "{} links {} to {}",
+                            carve.name(),
+                            c,
+                            n
+                        );
+                    }
+                }
+                let path = maze.solve().unwrap();
+                assert_eq!(path.first(), Some(&maze.start()));
+                assert_eq!(path.last(), maze.exit().as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn hex_mazes_are_perfect() {
+        assert_perfect(Hex::new(9, 7));
+    }
+
+    #[test]
+    fn triangle_mazes_are_perfect() {
+        assert_perfect(Triangle::new(11, 6));
+    }
+
+    #[test]
+    fn polar_mazes_are_perfect() {
+        assert_perfect(Polar::new(5));
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        let mut a = GraphMaze::new(Hex::new(9, 7), 4);
+        let mut b = GraphMaze::new(Hex::new(9, 7), 4);
+        a.generate();
+        b.generate();
+        assert_eq!(a.to_string(), b.to_string());
+    }
+}
@@ -0,0 +1,103 @@
+use super::{GraphMaze, Topology};
+
+// Pointy-top hexagons in rows, every odd row pushed half a cell to the right
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hex {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Hex {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn coords(&self, cell: usize) -> (usize, usize) {
+        (cell % self.width, cell / self.width)
+    }
+}
+
+impl Topology for Hex {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let (x, y) = self.coords(cell);
+        // West, east, then the two above and the two below, which lean right on odd rows
+        let shift = (y % 2) as isize;
+        [
+            (-1, 0),
+            (1, 0),
+            (shift - 1, -1),
+            (shift, -1),
+            (shift - 1, 1),
+            (shift, 1),
+        ]
+        .iter()
+        .filter_map(|&(dx, dy)| {
+            let nx = x.checked_add_signed(dx)?;
+            let ny = y.checked_add_signed(dy)?;
+            (nx < self.width && ny < self.height).then(|| self.cell(nx, ny))
+        })
+        .collect()
+    }
+
+    // Each cell is four characters wide and two lines tall:
+    //  / \
+    // | S |
+    //  \ /
+    fn render(&self, maze: &GraphMaze<Self>, marker: &dyn Fn(usize) -> Option<char>) -> String {
+        let cols = self.width * 4 + 3;
+        let mut canvas = vec![vec![' '; cols]; self.height * 2 + 1];
+
+        for cell in 0..self.len() {
+            let (x, y) = self.coords(cell);
+            let o = (y % 2) * 2 + x * 4;
+            let (top, mid, bottom) = (y * 2, y * 2 + 1, y * 2 + 2);
+            let shift = (y % 2) as isize;
+            let wall = |dx: isize, dy: isize| {
+                let Some(nx) = x.checked_add_signed(dx) else {
+                    return true;
+                };
+                let Some(ny) = y.checked_add_signed(dy) else {
+                    return true;
+                };
+                nx >= self.width || ny >= self.height || !maze.linked(cell, self.cell(nx, ny))
+            };
+
+            if wall(-1, 0) {
+                canvas[mid][o] = '|';
+            }
+            if wall(1, 0) {
+                canvas[mid][o + 4] = '|';
+            }
+            if wall(shift - 1, -1) {
+                canvas[top][o + 1] = '/';
+            }
+            if wall(shift, -1) {
+                canvas[top][o + 3] = '\\';
+            }
+            if wall(shift - 1, 1) {
+                canvas[bottom][o + 1] = '\\';
+            }
+            if wall(shift, 1) {
+                canvas[bottom][o + 3] = '/';
+            }
+            if let Some(c) = marker(cell) {
+                canvas[mid][o + 2] = c;
+            }
+        }
+
+        let mut out = String::new();
+        for line in canvas {
+            out.push_str(line.iter().collect::<String>().trim_end());
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
@@ -0,0 +1,153 @@
+use std::f64::consts::TAU;
+
+use super::{GraphMaze, Topology};
+
+// Concentric rings around a single centre cell. A ring splits each cell of the ring inside it
+// into as many as keeps the cells roughly square, so outer rings have more cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polar {
+    counts: Vec<usize>,  // cells per ring, the centre is ring 0
+    offsets: Vec<usize>, // id of the first cell of each ring
+}
+
+const PIXELS_PER_RING: usize = 3;
+
+impl Polar {
+    pub fn new(rings: usize) -> Self {
+        let mut counts = vec![];
+        for r in 0..rings {
+            if r == 0 {
+                counts.push(1);
+                continue;
+            }
+            let circumference = TAU * r as f64 / rings as f64;
+            let cell_width = circumference / counts[r - 1] as f64;
+            let ratio = (cell_width * rings as f64).round().max(1.0) as usize;
+            counts.push(counts[r - 1] * ratio);
+        }
+        let offsets = counts
+            .iter()
+            .scan(0, |total, &n| {
+                let first = *total;
+                *total += n;
+                Some(first)
+            })
+            .collect();
+        Self { counts, offsets }
+    }
+
+    pub fn rings(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn ring_len(&self, ring: usize) -> usize {
+        self.counts[ring]
+    }
+
+    pub fn cell(&self, ring: usize, index: usize) -> usize {
+        self.offsets[ring] + index
+    }
+
+    pub fn coords(&self, cell: usize) -> (usize, usize) {
+        let ring = self.offsets.partition_point(|&first| first <= cell) - 1;
+        (ring, cell - self.offsets[ring])
+    }
+
+    // Which cell a point lies in, given its distance from the centre in rings and its angle
+    fn locate(&self, radius: f64, angle: f64) -> Option<usize> {
+        let ring = radius.floor() as usize;
+        if ring >= self.rings() {
+            return None;
+        }
+        let n = self.counts[ring];
+        let index = ((angle / TAU) * n as f64).floor() as usize % n;
+        Some(self.cell(ring, index))
+    }
+}
+
+impl Topology for Polar {
+    fn len(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let (ring, index) = self.coords(cell);
+        let n = self.counts[ring];
+        let mut found = vec![];
+        if n > 1 {
+            found.push(self.cell(ring, (index + 1) % n));
+            if n > 2 {
+                found.push(self.cell(ring, (index + n - 1) % n));
+            }
+        }
+        if ring > 0 {
+            let ratio = n / self.counts[ring - 1];
+            found.push(self.cell(ring - 1, index / ratio));
+        }
+        if ring + 1 < self.rings() {
+            let ratio = self.counts[ring + 1] / n;
+            found.extend((0..ratio).map(|k| self.cell(ring + 1, index * ratio + k)));
+        }
+        found
+    }
+
+    // Rasterised onto square pixels printed two characters wide, like the square maze
+    fn render(&self, maze: &GraphMaze<Self>, marker: &dyn Fn(usize) -> Option<char>) -> String {
+        let k = PIXELS_PER_RING as f64;
+        let size = self.rings() * PIXELS_PER_RING * 2 + 3;
+        let centre = (size / 2) as f64;
+        let locate = |px: usize, py: usize| {
+            let (dx, dy) = (px as f64 - centre, py as f64 - centre);
+            let radius = (dx * dx + dy * dy).sqrt() / k;
+            let angle = dy.atan2(dx).rem_euclid(TAU);
+            (radius, self.locate(radius, angle))
+        };
+
+        let pixel = |px: usize, py: usize| {
+            let (radius, here) = locate(px, py);
+            match here {
+                None if radius < self.rings() as f64 + 1.0 / k => "##",
+                None => "  ",
+                Some(a) => {
+                    // The wall between two cells goes on the pixels of the higher id
+                    let wall = [(1, 0), (0, 1), (-1, 0), (0, -1)].iter().any(|&(dx, dy)| {
+                        let (Some(qx), Some(qy)) =
+                            (px.checked_add_signed(dx), py.checked_add_signed(dy))
+                        else {
+                            return false;
+                        };
+                        match locate(qx.min(size - 1), qy.min(size - 1)).1 {
+                            Some(b) => b < a && !maze.linked(a, b),
+                            None => false,
+                        }
+                    });
+                    if wall { "##" } else { "__" }
+                }
+            }
+        };
+        let mut canvas: Vec<Vec<String>> = (0..size)
+            .map(|py| (0..size).map(|px| pixel(px, py).to_string()).collect())
+            .collect();
+
+        for cell in 0..self.len() {
+            let Some(c) = marker(cell) else { continue };
+            let (ring, index) = self.coords(cell);
+            let (radius, angle) = if ring == 0 {
+                (0.0, 0.0)
+            } else {
+                let n = self.counts[ring] as f64;
+                ((ring as f64 + 0.5) * k, (index as f64 + 0.5) / n * TAU)
+            };
+            let px = (centre + radius * angle.cos()).round() as usize;
+            let py = (centre + radius * angle.sin()).round() as usize;
+            canvas[py][px] = format!("{}{}", c, c);
+        }
+
+        let mut out = String::new();
+        for line in canvas {
+            out.push_str(line.concat().trim_end());
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
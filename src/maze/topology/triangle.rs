@@ -0,0 +1,95 @@
+use super::{GraphMaze, Topology};
+
+// Rows of triangles that alternate pointing up and down, (0, 0) points up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Triangle {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Triangle {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn coords(&self, cell: usize) -> (usize, usize) {
+        (cell % self.width, cell / self.width)
+    }
+
+    pub fn points_up(&self, cell: usize) -> bool {
+        let (x, y) = self.coords(cell);
+        (x + y) % 2 == 0
+    }
+}
+
+impl Topology for Triangle {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    // Both slanted sides touch the row neighbours, the flat side touches the row below or above
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let (x, y) = self.coords(cell);
+        let mut found = vec![];
+        if x > 0 {
+            found.push(self.cell(x - 1, y));
+        }
+        if x + 1 < self.width {
+            found.push(self.cell(x + 1, y));
+        }
+        if self.points_up(cell) {
+            if y + 1 < self.height {
+                found.push(self.cell(x, y + 1));
+            }
+        } else if y > 0 {
+            found.push(self.cell(x, y - 1));
+        }
+        found
+    }
+
+    // One line per row of triangles, two characters per triangle. Slanted walls go between
+    // the triangles and a flat wall is drawn as `_` inside the triangle above it:
+    //    _   _
+    //   /_\ /_\ /
+    //   \ /_\ /_\
+    fn render(&self, maze: &GraphMaze<Self>, marker: &dyn Fn(usize) -> Option<char>) -> String {
+        let cols = self.width * 2 + 1;
+        let mut canvas = vec![vec![' '; cols]; self.height + 1];
+
+        // The flat tops of the first row's downward triangles
+        for x in 0..self.width {
+            if !self.points_up(self.cell(x, 0)) {
+                canvas[0][x * 2 + 1] = '_';
+            }
+        }
+
+        for cell in 0..self.len() {
+            let (x, y) = self.coords(cell);
+            let line = &mut canvas[y + 1];
+            let up = self.points_up(cell);
+            if x == 0 || !maze.linked(cell, cell - 1) {
+                line[x * 2] = if up { '/' } else { '\\' };
+            }
+            if x + 1 == self.width {
+                line[x * 2 + 2] = if up { '\\' } else { '/' };
+            }
+            if up && (y + 1 == self.height || !maze.linked(cell, self.cell(x, y + 1))) {
+                line[x * 2 + 1] = '_';
+            }
+            if let Some(c) = marker(cell) {
+                line[x * 2 + 1] = c;
+            }
+        }
+
+        let mut out = String::new();
+        for line in canvas {
+            out.push_str(line.iter().collect::<String>().trim_end());
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
@@ -0,0 +1,42 @@
+// Union-find over 0..n with path halving. Joining hangs the second set off the first one's
+// root.
+pub(super) struct Sets {
+    parent: Vec<usize>,
+}
+
+impl Sets {
+    pub(super) fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    pub(super) fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    // False when a and b were already in one set
+    pub(super) fn union(&mut self, a: usize, b: usize) -> bool {
+        let (ra, rb) = (self.find(a), self.find(b));
+        self.parent[rb] = ra;
+        ra != rb
+    }
+
+    // Every element back on its own
+    pub(super) fn reset(&mut self) {
+        for (i, p) in self.parent.iter_mut().enumerate() {
+            *p = i;
+        }
+    }
+}
+
+// splitmix64's finaliser, turns seeds that differ in a few bits into unrelated ones
+pub(super) fn splitmix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,323 @@
+use std::fmt;
+
+use super::{Cell, Grid, Maze, Pos, util::Sets};
+
+// Something wrong with a maze, each pointing at where it was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    // A passable cell on the outer wall other than the exit
+    OpenBorder(Pos),
+    // The start position doesn't hold a start cell
+    MissingStart(Pos),
+    // The maze has no exit at all
+    NoExit,
+    // The exit position doesn't hold an exit cell
+    MissingExit(Pos),
+    UnreachableExit(Pos),
+    // A region of passages cut off from the start, `at` is its first cell in reading order
+    Disconnected { at: Pos, cells: usize },
+    // The passage between `a` and `b` closes a loop, so the maze isn't perfect
+    Cycle { a: Pos, b: Pos },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::OpenBorder(pos) => write!(f, "outer wall is open at {}", pos),
+            Violation::MissingStart(pos) => write!(f, "no start cell at {}", pos),
+            Violation::NoExit => write!(f, "maze has no exit"),
+            Violation::MissingExit(pos) => write!(f, "no exit cell at {}", pos),
+            Violation::UnreachableExit(pos) => {
+                write!(f, "exit at {} can't be reached from the start", pos)
+            }
+            Violation::Disconnected { at, cells } => {
+                write!(f, "{} cells from {} are cut off from the start", cells, at)
+            }
+            Violation::Cycle { a, b } => write!(f, "passage from {} to {} makes a loop", a, b),
+        }
+    }
+}
+
+impl Maze {
+    // Every problem found, empty for a perfect maze with its start and exit in place. Mazes
+    // with loops on purpose (braided, dungeons, caves) will report them as `Cycle`s.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut found = vec![];
+        // A wrapping maze repeats its top and left edges along the bottom and right, so only
+        // the first copy counts
+        let (w, h) = if self.wrap {
+            self.period()
+        } else {
+            (self.x, self.y)
+        };
+        let passable = |p: Pos| self.grid[p.y][p.x].is_passable();
+
+        if !self.wrap {
+            for y in 0..self.y {
+                for x in 0..self.x {
+                    let pos = Pos::new(x, y);
+                    let on_border = x == 0 || y == 0 || x == self.x - 1 || y == self.y - 1;
+                    if on_border && passable(pos) && Some(pos) != self.exit {
+                        found.push(Violation::OpenBorder(pos));
+                    }
+                }
+            }
+        }
+
+        // Upper and lower floors of a `Maze3d` start on the stairs that lead into them
+        let start = self.try_get(self.start);
+        if !start.is_ok_and(|cell| cell == Cell::Start || cell.is_stairs()) {
+            found.push(Violation::MissingStart(self.start));
+        }
+        match self.exit {
+            None => found.push(Violation::NoExit),
+            Some(exit) if !self.contains(exit) || self.grid[exit.y][exit.x] != Cell::Exit => {
+                found.push(Violation::MissingExit(exit))
+            }
+            Some(_) => {}
+        }
+
+        // Union-find over the passages, joining each cell to the ones east and south of it.
+        // Any join between cells already in one set is a loop.
+        let index = |p: Pos| p.y * w + p.x;
+        let mut sets = Sets::new(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                let a = Pos::new(x, y);
+                if !passable(a) {
+                    continue;
+                }
+                for (dx, dy) in [(1, 0), (0, 1)] {
+                    let Some(b) = self.neighbour(a, dx, dy).filter(|&b| passable(b)) else {
+                        continue;
+                    };
+                    if !sets.union(index(a), index(b)) {
+                        found.push(Violation::Cycle { a, b });
+                    }
+                }
+            }
+        }
+
+        // Everything should hang off the start, or off the first passage when the start is
+        // missing, and the exit with it
+        let inside = |p: Pos| p.x < w && p.y < h && passable(p);
+        let first = (0..h)
+            .flat_map(|y| (0..w).map(move |x| Pos::new(x, y)))
+            .find(|&p| passable(p));
+        let Some(root) = Some(self.start).filter(|&p| inside(p)).or(first) else {
+            return found;
+        };
+        let root = sets.find(index(root));
+        if let Some(exit) = self.exit
+            && inside(exit)
+            && sets.find(index(exit)) != root
+        {
+            found.push(Violation::UnreachableExit(exit));
+        }
+        let mut region = vec![usize::MAX; w * h]; // set root to its place in `cut_off`
+        let mut cut_off = vec![];
+        for y in 0..h {
+            for x in 0..w {
+                let at = Pos::new(x, y);
+                if !passable(at) {
+                    continue;
+                }
+                let r = sets.find(index(at));
+                if r == root {
+                    continue;
+                }
+                if region[r] == usize::MAX {
+                    region[r] = cut_off.len();
+                    cut_off.push(Violation::Disconnected { at, cells: 0 });
+                }
+                if let Violation::Disconnected { cells, .. } = &mut cut_off[region[r]] {
+                    *cells += 1;
+                }
+            }
+        }
+        found.extend(cut_off);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{
+        ExitPlacement, MazeTrait,
+        generators::{self, RecursiveBacktracker},
+        levels::Maze3d,
+        sketch,
+    };
+
+    #[test]
+    fn a_perfect_maze_has_nothing_wrong() {
+        let maze = sketch(
+            "
+            #######
+            #S....#
+            #.###.#
+            #...#E#
+            #######
+            ",
+        );
+        assert_eq!(maze.validate(), vec![]);
+    }
+
+    #[test]
+    fn finds_a_hole_in_the_outer_wall() {
+        let maze = sketch(
+            "
+            #######
+            #S....#
+            ..###.#
+            #...#E#
+            #######
+            ",
+        );
+        assert_eq!(maze.validate(), vec![Violation::OpenBorder(Pos::new(0, 2))]);
+    }
+
+    #[test]
+    fn finds_the_passage_that_closes_a_loop() {
+        let maze = sketch(
+            "
+            #######
+            #S....#
+            #.###.#
+            #....E#
+            #######
+            ",
+        );
+        assert_eq!(
+            maze.validate(),
+            vec![Violation::Cycle {
+                a: Pos::new(4, 3),
+                b: Pos::new(5, 3)
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_passages_cut_off_from_the_start() {
+        let maze = sketch(
+            "
+            #######
+            #S..#.#
+            ###.#.#
+            #E..#.#
+            #######
+            ",
+        );
+        assert_eq!(
+            maze.validate(),
+            vec![Violation::Disconnected {
+                at: Pos::new(5, 1),
+                cells: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_an_exit_out_of_reach() {
+        let maze = sketch(
+            "
+            #######
+            #S..#E#
+            #####.#
+            #######
+            ",
+        );
+        assert_eq!(
+            maze.validate(),
+            vec![
+                Violation::UnreachableExit(Pos::new(5, 1)),
+                Violation::Disconnected {
+                    at: Pos::new(5, 1),
+                    cells: 2
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_a_missing_start_and_exit() {
+        let mut maze = sketch(
+            "
+            #####
+            #...#
+            #####
+            ",
+        );
+        assert_eq!(
+            maze.validate(),
+            vec![Violation::MissingStart(Pos::new(1, 1)), Violation::NoExit]
+        );
+        maze.exit = Some(Pos::new(3, 1));
+        assert_eq!(
+            maze.validate(),
+            vec![
+                Violation::MissingStart(Pos::new(1, 1)),
+                Violation::MissingExit(Pos::new(3, 1))
+            ]
+        );
+    }
+
+    #[test]
+    fn generated_mazes_validate() {
+        for mut generator in generators::all()
+            .into_iter()
+            .filter(|g| !matches!(g.name(), "dungeon" | "cave"))
+        {
+            for wrap in [false, true] {
+                if wrap && !generator.supports_wrap() {
+                    continue;
+                }
+                for (x, y) in [(21, 21), (31, 15), (20, 14)] {
+                    let mut maze = Maze::with_seed(x, y, 5);
+                    maze.set_wrap(wrap);
+                    maze.set_exit_placement(ExitPlacement::Farthest);
+                    maze.generate_with(generator.as_mut()).unwrap();
+                    assert_eq!(
+                        maze.validate(),
+                        vec![],
+                        "{} {}x{} wrap {}",
+                        generator.name(),
+                        x,
+                        y,
+                        wrap
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn added_loops_are_reported() {
+        let mut maze = Maze::with_seed(31, 21, 2);
+        maze.set_exit_placement(ExitPlacement::Farthest);
+        maze.generate_with(&mut RecursiveBacktracker).unwrap();
+        let added = maze.add_loops(0.2);
+        assert!(added > 0);
+        let found = maze.validate();
+        assert!(found.len() >= added);
+        assert!(found.iter().all(|v| matches!(v, Violation::Cycle { .. })));
+    }
+
+    #[test]
+    fn every_floor_has_a_start() {
+        let mut maze = Maze3d::new(11, 9, 3, 4);
+        maze.generate();
+        for z in 0..3 {
+            let found = maze.floor(z).validate();
+            assert!(
+                !found
+                    .iter()
+                    .any(|v| matches!(v, Violation::MissingStart(_))),
+                "floor {}: {:?}",
+                z,
+                found
+            );
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{Cell, Maze, MazeTrait, generators::RecursiveBacktracker, util::splitmix};
+
+pub type ChunkPos = (i64, i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct WorldPos {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl WorldPos {
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl std::fmt::Display for WorldPos {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+// An endless maze cut into square chunks of `chunk_size` cells a side. Every chunk is a perfect
+// maze generated from the world seed and its own coordinates, so it comes out the same each
+// time it is loaded, and neighbouring chunks share the wall between them with one door in it
+// that both sides agree on. Chunks are made when first touched and the least recently used
+// ones are dropped past `capacity`, to be made again the same way if they are needed later.
+pub struct World {
+    seed: u64,
+    chunk_size: usize,
+    capacity: usize,
+    chunks: HashMap<ChunkPos, Maze>,
+    recent: VecDeque<ChunkPos>, // least recently used first
+}
+
+impl World {
+    pub fn new(seed: u64, chunk_size: usize, capacity: usize) -> Self {
+        Self {
+            seed,
+            chunk_size: chunk_size.max(1),
+            capacity: capacity.max(1),
+            chunks: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_loaded(&self, chunk: ChunkPos) -> bool {
+        self.chunks.contains_key(&chunk)
+    }
+
+    // Grid cells a chunk spans in the world. Its right and bottom walls are the left and top
+    // walls of the next chunk over, so they don't count.
+    fn span(&self) -> i64 {
+        self.chunk_size as i64 * 2
+    }
+
+    pub fn chunk_of(&self, pos: WorldPos) -> ChunkPos {
+        (pos.x.div_euclid(self.span()), pos.y.div_euclid(self.span()))
+    }
+
+    pub fn chunk(&mut self, at: ChunkPos) -> &Maze {
+        if let Some(i) = self.recent.iter().position(|&c| c == at) {
+            self.recent.remove(i);
+        } else {
+            let maze = self.build(at);
+            self.chunks.insert(at, maze);
+            while self.recent.len() >= self.capacity {
+                if let Some(old) = self.recent.pop_front() {
+                    self.chunks.remove(&old);
+                }
+            }
+        }
+        self.recent.push_back(at);
+        &self.chunks[&at]
+    }
+
+    pub fn get_at(&mut self, pos: WorldPos) -> Cell {
+        let span = self.span();
+        let maze = self.chunk(self.chunk_of(pos));
+        maze.grid[pos.y.rem_euclid(span) as usize][pos.x.rem_euclid(span) as usize]
+    }
+
+    // Passable cells next to pos, crossing into neighbouring chunks where needed
+    pub fn open_neighbours(&mut self, pos: WorldPos) -> Vec<WorldPos> {
+        [(0, 1), (1, 0), (0, -1), (-1, 0)]
+            .iter()
+            .map(|&(dx, dy)| WorldPos::new(pos.x + dx, pos.y + dy))
+            .filter(|&n| self.get_at(n).is_passable())
+            .collect()
+    }
+
+    // A window of the world, `width` by `height` grid cells from `corner`
+    pub fn render(&mut self, corner: WorldPos, width: usize, height: usize) -> String {
+        let mut out = String::new();
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let cell = self.get_at(WorldPos::new(corner.x + x, corner.y + y));
+                out.push_str(cell.symbol());
+            }
+            out.push_str("\r\n");
+        }
+        out
+    }
+
+    fn build(&self, (cx, cy): ChunkPos) -> Maze {
+        let n = self.chunk_size;
+        let mut maze = Maze::with_seed(n * 2 + 1, n * 2 + 1, self.mix(cx, cy, 2));
+        maze.generate_with(&mut RecursiveBacktracker)
+            .expect("a plain square takes any generator");
+        let start = maze.start;
+        maze.grid[start.y][start.x] = Cell::Passage;
+
+        // Doors on the east and south edges belong to this chunk, the west and north ones to
+        // the chunks on the other side
+        let door = |key: u64| (key % n as u64) as usize * 2 + 1;
+        let east = door(self.mix(cx, cy, 0));
+        let west = door(self.mix(cx - 1, cy, 0));
+        let south = door(self.mix(cx, cy, 1));
+        let north = door(self.mix(cx, cy - 1, 1));
+        maze.grid[east][n * 2] = Cell::Passage;
+        maze.grid[west][0] = Cell::Passage;
+        maze.grid[n * 2][south] = Cell::Passage;
+        maze.grid[0][north] = Cell::Passage;
+        maze
+    }
+
+    // Stable hash of the world seed and a chunk
+    fn mix(&self, cx: i64, cy: i64, salt: u64) -> u64 {
+        splitmix(
+            self.seed
+                .wrapping_add((cx as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+                .wrapping_add((cy as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f))
+                .wrapping_add(salt.wrapping_mul(0x1656_67b1_9e37_79f9)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn chunks_join_up() {
+        for seed in 0..3 {
+            // Three by three chunks, counting the far walls so the doors through them show
+            let mut world = World::new(seed, 4, 16);
+            let side = world.span() * 3;
+            let inside = |p: WorldPos| (0..=side).contains(&p.x) && (0..=side).contains(&p.y);
+            let open: Vec<WorldPos> = (0..=side)
+                .flat_map(|y| (0..=side).map(move |x| WorldPos::new(x, y)))
+                .filter(|&p| world.get_at(p).is_passable())
+                .collect();
+
+            let mut seen = HashSet::from([open[0]]);
+            let mut stack = vec![open[0]];
+            while let Some(p) = stack.pop() {
+                for n in world.open_neighbours(p) {
+                    if inside(n) && seen.insert(n) {
+                        stack.push(n);
+                    }
+                }
+            }
+            assert_eq!(seen.len(), open.len(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn evicted_chunks_come_back_the_same() {
+        let mut world = World::new(7, 5, 2);
+        let first = world.chunk((0, -1)).to_string();
+        world.chunk((1, -1));
+        world.chunk((-3, 4));
+        assert!(!world.is_loaded((0, -1)));
+        assert_eq!(world.loaded(), 2);
+        assert_eq!(world.chunk((0, -1)).to_string(), first);
+
+        let mut other = World::new(7, 5, 8);
+        assert_eq!(other.chunk((0, -1)).to_string(), first);
+        assert_ne!(other.chunk((1, -1)).to_string(), first);
+    }
+
+    #[test]
+    fn recently_used_chunks_stay() {
+        let mut world = World::new(1, 3, 2);
+        world.chunk((0, 0));
+        world.chunk((1, 0));
+        world.chunk((0, 0));
+        world.chunk((2, 0));
+        assert!(world.is_loaded((0, 0)));
+        assert!(!world.is_loaded((1, 0)));
+    }
+}
@@ -0,0 +1,126 @@
+use super::{Maze, Pos};
+
+// A wrapping maze is a torus: stepping off one edge comes back in on the opposite one. The
+// outer ring of the grid becomes the wall line between the last and first cells, stored on
+// the top and left edges and copied onto the bottom and right ones so both sides of a tunnel
+// show up when the grid is printed. The torus is as wide and tall as the cell lattice, so on
+// an even sized grid the last column or row sits past the copy and stays solid wall.
+impl Maze {
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    // Takes effect on the next generate
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    // Grid columns and rows before the lattice repeats, two for every cell
+    pub(super) fn period(&self) -> (usize, usize) {
+        ((self.x - 1) / 2 * 2, (self.y - 1) / 2 * 2)
+    }
+
+    // The grid cell one step from `pos` going round the edges, for `Grid::neighbour`
+    pub(super) fn around(&self, pos: Pos, dx: isize, dy: isize) -> Pos {
+        let (w, h) = self.period();
+        Pos::new(
+            (pos.x % w + w).wrapping_add_signed(dx) % w,
+            (pos.y % h + h).wrapping_add_signed(dy) % h,
+        )
+    }
+
+    pub(super) fn mirror_edges(&mut self) {
+        if !self.wrap {
+            return;
+        }
+        let (w, h) = self.period();
+        for row in self.grid.iter_mut() {
+            row[w] = row[0];
+        }
+        self.grid[h] = self.grid[0].clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::maze::{
+        Cell, Generator, Grid, MazeTrait,
+        generators::{
+            AldousBroder, GrowingTree, HuntAndKill, Kruskal, Prim, RecursiveBacktracker, Selection,
+            Wilson,
+        },
+    };
+
+    // Within one period the open cells make a tree through the wrapped edges: all reachable
+    // from the start, with one fewer opening between neighbours than there are open cells
+    fn is_perfect_torus(maze: &Maze) -> bool {
+        let (w, h) = maze.period();
+        let open = |p: Pos| maze.grid[p.y][p.x].is_passable();
+        let cells: Vec<Pos> = (0..h)
+            .flat_map(|y| (0..w).map(move |x| Pos::new(x, y)))
+            .filter(|&p| open(p))
+            .collect();
+        let joins = cells
+            .iter()
+            .flat_map(|&p| [maze.neighbour(p, 1, 0), maze.neighbour(p, 0, 1)])
+            .filter(|&n| n.is_some_and(open))
+            .count();
+
+        let mut seen = HashSet::from([maze.start]);
+        let mut stack = vec![maze.start];
+        while let Some(p) = stack.pop() {
+            for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+                if let Some(n) = maze.neighbour(p, dx, dy)
+                    && open(n)
+                    && seen.insert(n)
+                {
+                    stack.push(n);
+                }
+            }
+        }
+        seen.len() == cells.len() && joins + 1 == cells.len()
+    }
+
+    #[test]
+    fn even_sizes_wrap_cleanly() {
+        let generators: Vec<Box<dyn Generator>> = vec![
+            Box::new(Prim),
+            Box::new(RecursiveBacktracker),
+            Box::new(Kruskal),
+            Box::new(HuntAndKill),
+            Box::new(GrowingTree::new(Selection::Random)),
+            Box::new(Wilson),
+            Box::new(AldousBroder),
+        ];
+        for mut generator in generators {
+            for (x, y) in [(20, 14), (22, 15), (21, 16), (21, 15)] {
+                for seed in 0..3 {
+                    let mut maze = Maze::with_seed(x, y, seed);
+                    maze.set_wrap(true);
+                    maze.generate_with(generator.as_mut()).unwrap();
+                    let name = format!("{} on {}x{} with seed {}", generator.name(), x, y, seed);
+                    assert!(is_perfect_torus(&maze), "{}\n{}", name, maze);
+
+                    // The copied edges match, and past them an even sized grid is solid
+                    let (w, h) = maze.period();
+                    for row in &maze.grid {
+                        assert_eq!(row[w], row[0], "{}", name);
+                        assert!(row[w + 1..].iter().all(|&c| c == Cell::Wall), "{}", name);
+                    }
+                    assert_eq!(maze.grid[h], maze.grid[0], "{}", name);
+                    assert!(
+                        maze.grid[h + 1..]
+                            .iter()
+                            .flatten()
+                            .all(|&c| c == Cell::Wall),
+                        "{}",
+                        name
+                    );
+                }
+            }
+        }
+    }
+}